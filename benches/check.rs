@@ -0,0 +1,54 @@
+//! Compares full parsing against tree-free syntax checking
+//!
+//! Run with `cargo bench --bench check`
+
+use acf_parser::check::check_acf;
+use acf_parser::prelude::*;
+use std::fmt::Write;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 100;
+const DEPOTS: usize = 2_000;
+
+/// Builds a manifest with a large `InstalledDepots` section
+fn large_manifest() -> String {
+    let mut src = String::from("\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n\t\"name\"\t\t\"Counter-Strike 2\"\n");
+    src.push_str("\t\"InstalledDepots\"\n\t{\n");
+    for depot in 0..DEPOTS {
+        let _ = write!(
+            src,
+            "\t\t\"{}\"\n\t\t{{\n\t\t\t\"manifest\"\t\t\"{}\"\n\t\t\t\"size\"\t\t\"{}\"\n\t\t}}\n",
+            depot,
+            depot * 7919,
+            depot * 31
+        );
+    }
+    src.push_str("\t}\n}\n");
+    src
+}
+
+fn report(label: &str, elapsed: Duration) {
+    println!(
+        "{:<20} {:>10.2?} total, {:>8.2?} per pass",
+        label,
+        elapsed,
+        elapsed / ITERATIONS
+    );
+}
+
+fn main() {
+    let src = large_manifest();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(parse_acf_str(black_box(&src)).unwrap());
+    }
+    report("parse", start.elapsed());
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        assert!(check_acf(black_box(&src)).is_empty());
+    }
+    report("check", start.elapsed());
+}
@@ -0,0 +1,22 @@
+//! Feeds arbitrary input to every parsing entry point
+//!
+//! Run with `cargo +nightly fuzz run parse` from the repository root
+
+#![no_main]
+
+use acf_parser::prelude::*;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let src = String::from_utf8_lossy(data);
+
+    if let Ok(acf) = parse_acf_str(&src) {
+        // Anything that parses must survive a round trip
+        let reparsed = parse_acf_str(&acf.to_vdf_string()).expect("serialized output must parse");
+        assert_eq!(reparsed, acf);
+    }
+
+    if let Ok(lazy) = parse_acf_lazy(&src) {
+        let _ = lazy.to_acf();
+    }
+});
@@ -0,0 +1,90 @@
+#ifndef ACF_PARSER_H
+#define ACF_PARSER_H
+
+/* Warning: this file is autogenerated by cbindgen. Do not modify manually. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/*
+ The call succeeded
+ */
+#define ACF_OK 0
+
+/*
+ The requested path doesn't exist in the document
+ */
+#define ACF_ERR_NOT_FOUND -1
+
+/*
+ A required pointer was null or a string wasn't valid UTF-8
+ */
+#define ACF_ERR_INVALID_ARGUMENT -2
+
+/*
+ The file couldn't be read or parsed
+ */
+#define ACF_ERR_PARSE -3
+
+/*
+ A panic was caught inside the library
+ */
+#define ACF_ERR_PANIC -4
+
+/*
+ Opaque handle to a parsed document
+ */
+typedef struct AcfHandle AcfHandle;
+
+/*
+ Parses the ACF file at `path`
+
+ On success, stores a new handle in `*out_handle` and returns [`ACF_OK`]. On failure, stores
+ null in `*out_handle` (when it isn't null itself) and returns a negative error code
+
+ # Safety
+
+ `path` must be null or point to a NUL-terminated string. `out_handle` must be null or valid
+ for writes
+ */
+int32_t acf_parse_file(const char *path, AcfHandle **out_handle);
+
+/*
+ Looks up a value by its slash separated path (e.g. `AppState/UserConfig/language`)
+
+ The first segment names a root entry, the last names an expression, and any in between name
+ nested entries. The first match is used at every level. Returns the value's length, writing
+ it to `buf` if it fits (see the module documentation), or a negative error code
+
+ # Safety
+
+ `handle` must be null or a live handle from [`acf_parse_file`]. `path` must be null or point
+ to a NUL-terminated string. `buf` must be null or valid for writes of `len` bytes
+ */
+ptrdiff_t acf_get(const AcfHandle *handle, const char *path, char *buf, size_t len);
+
+/*
+ Retrieves the description of the most recent error on the calling thread
+
+ Returns the description's length, writing it to `buf` if it fits (see the module
+ documentation). Returns zero if no error has occurred
+
+ # Safety
+
+ `buf` must be null or valid for writes of `len` bytes
+ */
+ptrdiff_t acf_last_error(char *buf, size_t len);
+
+/*
+ Releases a handle returned by [`acf_parse_file`]. Passing null is a no-op
+
+ # Safety
+
+ `handle` must be null or a live handle from [`acf_parse_file`] that hasn't been freed
+ */
+void acf_free(AcfHandle *handle);
+
+#endif  /* ACF_PARSER_H */
@@ -0,0 +1,263 @@
+use crate::errors::*;
+use crate::parser::{Acf, Entry, DEFAULT_MAX_DEPTH};
+use crate::span::Span;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// Marks a nested map
+const TYPE_MAP: u8 = 0x00;
+
+/// Marks a NUL-terminated string value
+const TYPE_STRING: u8 = 0x01;
+
+/// Marks a little endian 32-bit signed integer value
+const TYPE_INT32: u8 = 0x02;
+
+/// Marks a little endian 32-bit float value
+const TYPE_FLOAT32: u8 = 0x03;
+
+/// Marks a little endian 64-bit unsigned integer value
+const TYPE_UINT64: u8 = 0x07;
+
+/// Marks the end of a map
+const TYPE_END: u8 = 0x08;
+
+/// Marks a little endian 64-bit signed integer value
+const TYPE_INT64: u8 = 0x0A;
+
+/// Parses the contents of Steam's `shortcuts.vdf`, which lists non-Steam games added to the
+/// library
+///
+/// The file is binary VDF: a root `shortcuts` map holding one map per shortcut, named by its
+/// index (`"0"`, `"1"`, ...), with values such as `appid`, `AppName`, and `Exe` and a nested
+/// `tags` map. The result has a single `shortcuts` root entry with a sub-entry per shortcut.
+/// Integers and floats are written out as decimal strings, so `appid` reads as a (possibly
+/// negative) signed 32-bit number, as Steam stores it
+///
+/// Truncated or malformed input is reported as a syntax error whose span holds byte offsets into
+/// `bytes`
+pub fn parse_shortcuts(bytes: &[u8]) -> Result<Acf> {
+    let acf = parse_binary(bytes)?;
+
+    match acf.entries.as_slice() {
+        [root] if root.name.eq_ignore_ascii_case("shortcuts") => Ok(acf),
+        _ => Err(syntax_error(0, bytes.len(), "expected a single 'shortcuts' map")),
+    }
+}
+
+/// Decodes a binary VDF document
+///
+/// Root values outside of any map have nowhere to live in the tree, so they are rejected
+fn parse_binary(bytes: &[u8]) -> Result<Acf> {
+    let mut reader = Reader { bytes, pos: 0 };
+    let mut entries = Vec::new();
+
+    while let Some(kind) = reader.next_byte() {
+        match kind {
+            TYPE_MAP => {
+                let name = reader.string()?;
+                entries.push(reader.map(name, 1)?);
+            }
+            TYPE_END if reader.pos == bytes.len() => break,
+            _ => {
+                let start = reader.pos - 1;
+                return Err(syntax_error(start, reader.pos, "expected a map at the root"));
+            }
+        }
+    }
+
+    Ok(Acf { entries })
+}
+
+/// Cursor over binary VDF input
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct Reader<'a> {
+    /// Input being decoded
+    bytes: &'a [u8],
+
+    /// Offset of the next unread byte
+    pos: usize,
+}
+
+impl Reader<'_> {
+    /// Reads a single byte
+    fn next_byte(&mut self) -> Option<u8> {
+        let byte = *self.bytes.get(self.pos)?;
+        self.pos += 1;
+
+        Some(byte)
+    }
+
+    /// Reads exactly `N` bytes
+    fn take<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let end = self.pos + N;
+        let Some(val) = self.bytes.get(self.pos..end) else {
+            return Err(syntax_error(self.pos, self.bytes.len(), "unexpected end of input"));
+        };
+        self.pos = end;
+
+        Ok(val.try_into().unwrap_or([0; N]))
+    }
+
+    /// Reads a NUL-terminated UTF-8 string
+    fn string(&mut self) -> Result<String> {
+        let start = self.pos;
+        let Some(len) = self.bytes[start..].iter().position(|&byte| byte == 0) else {
+            return Err(syntax_error(start, self.bytes.len(), "unterminated string"));
+        };
+        self.pos += len + 1;
+
+        match std::str::from_utf8(&self.bytes[start..start + len]) {
+            Ok(val) => Ok(val.to_owned()),
+            Err(_) => Err(syntax_error(start, start + len, "string is not valid UTF-8")),
+        }
+    }
+
+    /// Reads the contents of a map up to and including its end marker
+    fn map(&mut self, name: String, depth: usize) -> Result<Entry> {
+        if depth > DEFAULT_MAX_DEPTH {
+            let span = Span::new(self.pos, self.pos);
+            let err = ParseError::limit_exceeded(Limit::Depth, DEFAULT_MAX_DEPTH, span);
+            return Err(AcfError::Parse(err));
+        }
+
+        let mut expressions = Vec::new();
+        let mut entries = Vec::new();
+
+        loop {
+            let start = self.pos;
+            let Some(kind) = self.next_byte() else {
+                return Err(syntax_error(start, start, "expected the end of a map"));
+            };
+            if kind == TYPE_END {
+                break;
+            }
+
+            let key = self.string()?;
+            let value = match kind {
+                TYPE_MAP => {
+                    entries.push(self.map(key, depth + 1)?);
+                    continue;
+                }
+                TYPE_STRING => self.string()?,
+                TYPE_INT32 => i32::from_le_bytes(self.take()?).to_string(),
+                TYPE_FLOAT32 => f32::from_le_bytes(self.take()?).to_string(),
+                TYPE_UINT64 => u64::from_le_bytes(self.take()?).to_string(),
+                TYPE_INT64 => i64::from_le_bytes(self.take()?).to_string(),
+                _ => {
+                    let message = format!("unknown value type 0x{:02x}", kind);
+                    return Err(syntax_error(start, start + 1, message));
+                }
+            };
+            expressions.push((key, value));
+        }
+
+        Ok(Entry {
+            name,
+            expressions: expressions.into_iter().collect(),
+            entries,
+            comments: Vec::new(),
+        })
+    }
+}
+
+/// Creates a syntax error spanning the given byte offsets
+fn syntax_error(start: usize, end: usize, message: impl Into<String>) -> AcfError {
+    AcfError::Parse(ParseError::syntax(Span::new(start, end), message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Appends a string value
+    fn string(out: &mut Vec<u8>, key: &str, value: &str) {
+        out.push(TYPE_STRING);
+        out.extend_from_slice(key.as_bytes());
+        out.push(0);
+        out.extend_from_slice(value.as_bytes());
+        out.push(0);
+    }
+
+    /// Appends the start of a map
+    fn map(out: &mut Vec<u8>, name: &str) {
+        out.push(TYPE_MAP);
+        out.extend_from_slice(name.as_bytes());
+        out.push(0);
+    }
+
+    /// Builds a `shortcuts.vdf` blob with two shortcuts
+    fn shortcuts() -> Vec<u8> {
+        let mut out = Vec::new();
+        map(&mut out, "shortcuts");
+
+        map(&mut out, "0");
+        out.push(TYPE_INT32);
+        out.extend_from_slice(b"appid\0");
+        out.extend_from_slice(&(-1_234_567_890_i32).to_le_bytes());
+        string(&mut out, "AppName", "Heroic Games Launcher");
+        string(&mut out, "Exe", "\"/usr/bin/heroic\"");
+        string(&mut out, "StartDir", "\"/usr/bin/\"");
+        out.push(TYPE_INT32);
+        out.extend_from_slice(b"LastPlayTime\0");
+        out.extend_from_slice(&1_700_000_000_i32.to_le_bytes());
+        map(&mut out, "tags");
+        string(&mut out, "0", "favorite");
+        string(&mut out, "1", "launchers");
+        out.push(TYPE_END);
+        out.push(TYPE_END);
+
+        map(&mut out, "1");
+        string(&mut out, "AppName", "RetroArch");
+        string(&mut out, "Exe", "retroarch");
+        map(&mut out, "tags");
+        out.push(TYPE_END);
+        out.push(TYPE_END);
+
+        out.push(TYPE_END);
+        out.push(TYPE_END);
+        out
+    }
+
+    #[test]
+    fn decode_shortcuts() {
+        let acf = parse_shortcuts(&shortcuts()).unwrap();
+        assert_eq!(acf.entries.len(), 1);
+
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.name, "shortcuts");
+        assert_eq!(root_entry.child_names(), ["0", "1"]);
+
+        let heroic = &root_entry.entries[0];
+        assert_eq!(heroic.expressions["appid"], "-1234567890");
+        assert_eq!(heroic.expressions["AppName"], "Heroic Games Launcher");
+        assert_eq!(heroic.expressions["Exe"], "\"/usr/bin/heroic\"");
+        assert_eq!(heroic.expressions["LastPlayTime"], "1700000000");
+        assert_eq!(heroic.entries[0].name, "tags");
+        assert_eq!(heroic.entries[0].expressions["1"], "launchers");
+
+        let retroarch = &root_entry.entries[1];
+        assert_eq!(retroarch.expressions["AppName"], "RetroArch");
+        assert!(retroarch.entries[0].expressions.is_empty());
+    }
+
+    #[test]
+    fn malformed() {
+        let bytes = shortcuts();
+        for len in 0..bytes.len() - 1 {
+            let err = parse_shortcuts(&bytes[..len]).unwrap_err();
+            assert!(err.as_parse_error().unwrap().is_syntax());
+        }
+
+        let mut bytes = shortcuts();
+        bytes[1..10].copy_from_slice(b"something");
+        assert!(parse_shortcuts(&bytes).is_err());
+
+        let mut bytes = shortcuts();
+        bytes[14] = 0x05;
+        let err = parse_shortcuts(&bytes).unwrap_err();
+        assert_eq!(err.as_parse_error().unwrap().span(), Some(Span::new(14, 15)));
+    }
+}
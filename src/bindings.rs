@@ -0,0 +1,171 @@
+//! Build and install into the active virtualenv with maturin, then run the Python tests:
+//!
+//! ```text
+//! maturin develop --features python
+//! pytest tests/python
+//! ```
+//!
+//! The extension targets the stable ABI (`abi3`), so one wheel works on CPython 3.8 and later
+
+// pyo3 0.22's function macros convert `PyResult` errors into `PyErr` with a redundant `.into()`
+#![allow(clippy::useless_conversion)]
+
+use crate::errors::{AcfError, ParseError};
+use crate::line_index::LineIndex;
+use crate::parser::{parse_acf_str_indexed, Entry};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyOSError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::fs;
+
+create_exception!(
+    acf_parser,
+    AcfParseError,
+    PyException,
+    "Raised when ACF content can't be parsed. `line` and `column` are 1-based, or `None` when \
+     the error has no location"
+);
+
+/// Parses the ACF file at `path` into nested dicts
+#[pyfunction]
+#[pyo3(name = "parse_acf")]
+fn py_parse_acf<'py>(py: Python<'py>, path: &str) -> PyResult<Bound<'py, PyDict>> {
+    let src = read(path)?;
+    py_parse_acf_str(py, &src)
+}
+
+/// Parses ACF text into nested dicts
+///
+/// Root entries map to dicts of their contents. Within an entry, expressions map keys to string
+/// values and sub-entries map names to dicts. Expressions and sub-entries share one namespace,
+/// and the first occurrence of a repeated name wins
+#[pyfunction]
+#[pyo3(name = "parse_acf_str")]
+fn py_parse_acf_str<'py>(py: Python<'py>, text: &str) -> PyResult<Bound<'py, PyDict>> {
+    let entries = parse(py, text)?;
+
+    let dict = PyDict::new_bound(py);
+    for entry in &entries {
+        if !dict.contains(&entry.name)? {
+            dict.set_item(&entry.name, entry_to_dict(py, entry)?)?;
+        }
+    }
+
+    Ok(dict)
+}
+
+/// Typed view of a Steam app manifest's `AppState` entry
+#[pyclass(frozen, module = "acf_parser")]
+struct AppManifest {
+    /// Steam app ID
+    #[pyo3(get)]
+    appid: u32,
+
+    /// Display name of the app
+    #[pyo3(get)]
+    name: String,
+
+    /// Installed size in bytes (`SizeOnDisk`)
+    #[pyo3(get)]
+    size: u64,
+}
+
+#[pymethods]
+impl AppManifest {
+    /// Reads an app manifest from the file at `path`
+    #[staticmethod]
+    fn from_file(py: Python<'_>, path: &str) -> PyResult<Self> {
+        let src = read(path)?;
+        Self::from_text(py, &src)
+    }
+
+    /// Reads an app manifest from ACF text
+    #[staticmethod]
+    fn from_text(py: Python<'_>, text: &str) -> PyResult<Self> {
+        let entries = parse(py, text)?;
+        let app_state = entries
+            .iter()
+            .find(|entry| entry.name == "AppState")
+            .ok_or_else(|| PyValueError::new_err("no 'AppState' entry found"))?;
+
+        let value_err = |e: AcfError| PyValueError::new_err(e.to_string());
+        Ok(Self {
+            appid: app_state.parse_value("appid").map_err(value_err)?,
+            name: app_state.parse_value("name").map_err(value_err)?,
+            size: app_state.parse_value("SizeOnDisk").map_err(value_err)?,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!("AppManifest(appid={}, name={:?}, size={})", self.appid, self.name, self.size)
+    }
+}
+
+/// Python module definition
+#[pymodule]
+fn acf_parser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(py_parse_acf, m)?)?;
+    m.add_function(wrap_pyfunction!(py_parse_acf_str, m)?)?;
+    m.add_class::<AppManifest>()?;
+    m.add("AcfParseError", m.py().get_type_bound::<AcfParseError>())?;
+
+    Ok(())
+}
+
+/// Reads a file, raising `OSError` on failure
+fn read(path: &str) -> PyResult<String> {
+    fs::read_to_string(path)
+        .map_err(|e| PyOSError::new_err(format!("failed to read '{}': {}", path, e)))
+}
+
+/// Parses ACF text, raising [`AcfParseError`] on failure
+fn parse(py: Python<'_>, text: &str) -> PyResult<Vec<Entry>> {
+    let (result, index) = parse_acf_str_indexed(text);
+
+    match result {
+        Ok(acf) => Ok(acf.entries),
+        Err(e) => Err(parse_error(py, &e, &index)),
+    }
+}
+
+/// Converts an error into an [`AcfParseError`] carrying its message and location
+fn parse_error(py: Python<'_>, err: &AcfError, index: &LineIndex) -> PyErr {
+    let message = match err.as_parse_error() {
+        Some(e) => e.to_string(),
+        None => err.to_string(),
+    };
+    let (line, column) = match err.as_parse_error().and_then(ParseError::span) {
+        Some(span) => {
+            let (line, column) = index.line_col(span.start);
+            (Some(line), Some(column))
+        }
+        None => (None, None),
+    };
+
+    let py_err = AcfParseError::new_err(message);
+    let value = py_err.value_bound(py);
+    // Setting attributes on a freshly created exception instance can't fail
+    let _ = value.setattr("line", line);
+    let _ = value.setattr("column", column);
+
+    py_err
+}
+
+/// Converts an entry into a dict
+fn entry_to_dict<'py>(py: Python<'py>, entry: &Entry) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+
+    for (key, value) in &entry.expressions {
+        if !dict.contains(key)? {
+            dict.set_item(key, value)?;
+        }
+    }
+    for sub_entry in &entry.entries {
+        if !dict.contains(&sub_entry.name)? {
+            dict.set_item(&sub_entry.name, entry_to_dict(py, sub_entry)?)?;
+        }
+    }
+
+    Ok(dict)
+}
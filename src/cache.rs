@@ -0,0 +1,437 @@
+//! Enable with the `cache` feature. The encoding is private to this crate and only meant to be
+//! read back by the same version that wrote it; it is not an interchange format
+//!
+//! # Layout
+//!
+//! A cache blob starts with the magic bytes `ACFC` and a little endian `u16` format version,
+//! followed by the root entries. Every list and string is prefixed with its length as an LEB128
+//! varint, and an entry is its name, its expressions as key-value pairs, its captured comments as
+//! key-comment pairs, then its sub-entries
+
+use crate::errors::*;
+use crate::expressions::Expressions;
+use crate::parser::{parse_acf_str, Acf, Entry, DEFAULT_MAX_DEPTH};
+use crate::span::Span;
+use crate::stamp::{fnv1a, load, Stamp};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// Magic bytes at the start of every cache blob
+const MAGIC: &[u8; 4] = b"ACFC";
+
+/// Version of the encoding, bumped whenever the layout changes
+pub const CACHE_VERSION: u16 = 2;
+
+/// Length in bytes of the source stamp at the start of a cache file
+const STAMP_LEN: usize = 28;
+
+impl Acf {
+    /// Encodes the document in the compact binary cache format
+    ///
+    /// Decoding the result with [`Acf::from_cache_bytes`] is much faster than parsing the
+    /// equivalent text, as nothing needs to be tokenized or unescaped
+    pub fn to_cache_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&CACHE_VERSION.to_le_bytes());
+        write_entries(&mut out, &self.entries);
+
+        out
+    }
+
+    /// Decodes a document produced by [`Acf::to_cache_bytes`]
+    ///
+    /// Blobs with the wrong magic bytes, a different [`CACHE_VERSION`], or content that is
+    /// truncated or corrupted are rejected with a syntax error whose span holds byte offsets into
+    /// `bytes`. Nesting deeper than [`DEFAULT_MAX_DEPTH`] is rejected too
+    pub fn from_cache_bytes(bytes: &[u8]) -> Result<Acf> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(MAGIC.len())? != MAGIC {
+            return Err(syntax_error(0, MAGIC.len(), "not an acf-parser cache"));
+        }
+
+        let version = reader.take(2)?;
+        let version = u16::from_le_bytes([version[0], version[1]]);
+        if version != CACHE_VERSION {
+            let message = format!("unsupported cache version {}", version);
+            return Err(syntax_error(MAGIC.len(), MAGIC.len() + 2, message));
+        }
+
+        let entries = reader.entries(0)?;
+        if reader.pos != bytes.len() {
+            return Err(syntax_error(reader.pos, bytes.len(), "trailing bytes"));
+        }
+
+        Ok(Acf { entries })
+    }
+}
+
+/// Parses the ACF file at `path`, reusing a cached copy of the tree from `cache_dir` when the
+/// file hasn't changed
+///
+/// Each source file has its own cache file, named after a hash of its path, that records the
+/// source's modification time, length, and content hash. If the time and length match, the tree
+/// is loaded from the cache without reading the source at all. Otherwise the source is read, and
+/// if its content hash still matches (e.g. it was only touched) the cached tree is used anyway.
+/// On a miss, a stale entry, or an unreadable or corrupted cache, the source is parsed as usual
+/// and the cache rewritten
+///
+/// The cache is best effort: failing to create `cache_dir` or write to it never fails the parse
+pub fn parse_acf_cached(path: &str, cache_dir: &str) -> Result<Acf> {
+    let metadata = Stamp::of(path)?;
+    let cache_file = cache_path(path, cache_dir);
+    let cached = fs::read(&cache_file).ok().filter(|bytes| bytes.len() >= STAMP_LEN);
+
+    if let Some(bytes) = &cached {
+        if decode_stamp(&bytes[..STAMP_LEN]).matches_metadata(&metadata) {
+            if let Ok(acf) = Acf::from_cache_bytes(&bytes[STAMP_LEN..]) {
+                return Ok(acf);
+            }
+        }
+    }
+
+    let (contents, stamp) = load(path)?;
+
+    if let Some(bytes) = &cached {
+        let cached_stamp = decode_stamp(&bytes[..STAMP_LEN]);
+        if cached_stamp.len == stamp.len && cached_stamp.hash == stamp.hash {
+            if let Ok(acf) = Acf::from_cache_bytes(&bytes[STAMP_LEN..]) {
+                write_cache(&cache_file, &stamp, &bytes[STAMP_LEN..]);
+                return Ok(acf);
+            }
+        }
+    }
+
+    let acf = parse_acf_str(&contents)?;
+    write_cache(&cache_file, &stamp, &acf.to_cache_bytes());
+
+    Ok(acf)
+}
+
+/// Decodes a source stamp from exactly [`STAMP_LEN`] bytes
+///
+/// The stamp is the source's length, the whole seconds and nanoseconds of its modification time
+/// since the Unix epoch, and the hash of its contents
+fn decode_stamp(bytes: &[u8]) -> Stamp {
+    let u64_at = |at: usize| {
+        let mut val = [0; 8];
+        val.copy_from_slice(&bytes[at..at + 8]);
+        u64::from_le_bytes(val)
+    };
+    let nanos = u32::from_le_bytes([bytes[16], bytes[17], bytes[18], bytes[19]]);
+    let since_epoch = Duration::new(u64_at(8), nanos);
+
+    Stamp {
+        modified: UNIX_EPOCH.checked_add(since_epoch).unwrap_or(UNIX_EPOCH),
+        len: u64_at(0),
+        hash: u64_at(20),
+    }
+}
+
+/// Encodes a source stamp into [`STAMP_LEN`] bytes, as read by [`decode_stamp`]
+fn encode_stamp(stamp: &Stamp, out: &mut Vec<u8>) {
+    let since_epoch = stamp.modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+
+    out.extend_from_slice(&stamp.len.to_le_bytes());
+    out.extend_from_slice(&since_epoch.as_secs().to_le_bytes());
+    out.extend_from_slice(&since_epoch.subsec_nanos().to_le_bytes());
+    out.extend_from_slice(&stamp.hash.to_le_bytes());
+}
+
+/// Returns the cache file for a source path
+fn cache_path(path: &str, cache_dir: &str) -> PathBuf {
+    let source = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+    let name = format!("{:016x}.acfc", fnv1a(source.to_string_lossy().as_bytes()));
+
+    Path::new(cache_dir).join(name)
+}
+
+/// Writes a cache file, replacing any previous one in a single step. Errors are ignored
+fn write_cache(cache_file: &Path, stamp: &Stamp, tree: &[u8]) {
+    let mut contents = Vec::with_capacity(STAMP_LEN + tree.len());
+    encode_stamp(stamp, &mut contents);
+    contents.extend_from_slice(tree);
+
+    if let Some(dir) = cache_file.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+
+    let temp = cache_file.with_extension(format!("{}.tmp", std::process::id()));
+    if fs::write(&temp, &contents).is_err() || fs::rename(&temp, cache_file).is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+}
+
+/// Writes a length as an LEB128 varint
+fn write_len(out: &mut Vec<u8>, mut len: usize) {
+    while len >= 0x80 {
+        out.push((len as u8 & 0x7F) | 0x80);
+        len >>= 7;
+    }
+    out.push(len as u8);
+}
+
+/// Writes a length prefixed string
+fn write_str(out: &mut Vec<u8>, val: &str) {
+    write_len(out, val.len());
+    out.extend_from_slice(val.as_bytes());
+}
+
+/// Writes a length prefixed list of entries
+fn write_entries(out: &mut Vec<u8>, entries: &[Entry]) {
+    write_len(out, entries.len());
+
+    for entry in entries {
+        write_str(out, &entry.name);
+
+        write_len(out, entry.expressions.len());
+        for (key, value) in &entry.expressions {
+            write_str(out, key);
+            write_str(out, value);
+        }
+
+        write_len(out, entry.comments.len());
+        for (key, comment) in &entry.comments {
+            write_str(out, key);
+            write_str(out, comment);
+        }
+
+        write_entries(out, &entry.entries);
+    }
+}
+
+/// Cursor over a cache blob
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct Reader<'a> {
+    /// Blob being decoded
+    bytes: &'a [u8],
+
+    /// Offset of the next unread byte
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Reads exactly `len` bytes
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let Some(val) = self.pos.checked_add(len).and_then(|end| self.bytes.get(self.pos..end))
+        else {
+            return Err(syntax_error(self.pos, self.bytes.len(), "unexpected end of input"));
+        };
+        self.pos += len;
+
+        Ok(val)
+    }
+
+    /// Reads an LEB128 varint length
+    fn read_len(&mut self) -> Result<usize> {
+        let start = self.pos;
+        let mut len = 0usize;
+
+        for shift in (0..usize::BITS).step_by(7) {
+            let byte = self.take(1)?[0];
+            len |= usize::from(byte & 0x7F).checked_shl(shift).unwrap_or(0);
+            if byte & 0x80 == 0 {
+                return Ok(len);
+            }
+        }
+
+        Err(syntax_error(start, self.pos, "length is too large"))
+    }
+
+    /// Reads a length prefixed string
+    fn string(&mut self) -> Result<String> {
+        let len = self.read_len()?;
+        let start = self.pos;
+
+        match std::str::from_utf8(self.take(len)?) {
+            Ok(val) => Ok(val.to_owned()),
+            Err(_) => Err(syntax_error(start, self.pos, "string is not valid UTF-8")),
+        }
+    }
+
+    /// Reads a length prefixed list of entries nested `depth` levels deep
+    fn entries(&mut self, depth: usize) -> Result<Vec<Entry>> {
+        if depth > DEFAULT_MAX_DEPTH {
+            let span = Span::new(self.pos, self.pos);
+            let err = ParseError::limit_exceeded(Limit::Depth, DEFAULT_MAX_DEPTH, span);
+            return Err(AcfError::Parse(err));
+        }
+
+        // Every entry takes at least four bytes, which bounds allocations for corrupted counts
+        let count = self.read_len()?;
+        let mut entries = Vec::with_capacity(count.min(self.remaining() / 4));
+
+        for _ in 0..count {
+            let name = self.string()?;
+
+            let expression_count = self.read_len()?;
+            let mut expressions = Vec::with_capacity(expression_count.min(self.remaining() / 2));
+            for _ in 0..expression_count {
+                expressions.push((self.string()?, self.string()?));
+            }
+
+            let comment_count = self.read_len()?;
+            let mut comments = Vec::with_capacity(comment_count.min(self.remaining() / 2));
+            for _ in 0..comment_count {
+                comments.push((self.string()?, self.string()?));
+            }
+
+            entries.push(Entry {
+                name,
+                expressions: expressions.into_iter().collect::<Expressions>(),
+                entries: self.entries(depth + 1)?,
+                comments,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Returns the number of unread bytes
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+}
+
+/// Creates a syntax error spanning the given byte offsets
+fn syntax_error(start: usize, end: usize, message: impl Into<String>) -> AcfError {
+    AcfError::Parse(ParseError::syntax(Span::new(start, end), message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_acf, parse_acf_str_with_options, ParseOptions};
+    use crate::stamp::temp_copy;
+
+    /// Copies a fixture to a temporary path unique to the test, returning it and an empty cache
+    /// directory next to it
+    fn temp_cache(name: &str) -> (String, String) {
+        let path = temp_copy(&format!("cache-{}", name));
+        let cache_dir = path.with_extension("");
+        let _ = fs::remove_dir_all(&cache_dir);
+
+        (path.to_string_lossy().into(), cache_dir.to_string_lossy().into())
+    }
+
+    /// Removes the files created by [`temp_cache`]
+    fn clean_up(path: &str, cache_dir: &str) {
+        let _ = fs::remove_file(path);
+        let _ = fs::remove_dir_all(cache_dir);
+    }
+
+    #[test]
+    fn round_trip() {
+        for fixture in ["730", "745", "228980", "250820"] {
+            let acf = parse_acf(&format!("./acfs/appmanifest_{}.acf", fixture)).unwrap();
+            assert_eq!(Acf::from_cache_bytes(&acf.to_cache_bytes()).unwrap(), acf);
+        }
+
+        let options = ParseOptions {
+            capture_comments: true,
+            ..Default::default()
+        };
+        let src = fs::read_to_string("./acfs/appmanifest_730.acf").unwrap();
+        let src = src.replacen("\t\"appid\"", "\t// the app's ID\n\t\"appid\"", 1);
+        let acf = parse_acf_str_with_options(&src, &options).unwrap();
+        let decoded = Acf::from_cache_bytes(&acf.to_cache_bytes()).unwrap();
+        assert_eq!(decoded.entries[0].comment_for("appid"), Some("the app's ID"));
+        assert_eq!(decoded, acf);
+    }
+
+    #[test]
+    fn rejects_corrupted_bytes() {
+        let bytes = parse_acf("./acfs/appmanifest_730.acf").unwrap().to_cache_bytes();
+        for len in 0..bytes.len() {
+            assert!(Acf::from_cache_bytes(&bytes[..len]).is_err());
+        }
+
+        let mut wrong_version = bytes.clone();
+        wrong_version[4] = wrong_version[4].wrapping_add(1);
+        let err = Acf::from_cache_bytes(&wrong_version).unwrap_err();
+        match err.as_parse_error() {
+            Some(ParseError::Syntax { span, message }) => {
+                assert_eq!(*span, Span::new(MAGIC.len(), MAGIC.len() + 2));
+                assert!(message.contains("unsupported cache version"));
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(Acf::from_cache_bytes(&wrong_magic).is_err());
+
+        let mut huge_count = bytes[..6].to_vec();
+        huge_count.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert!(Acf::from_cache_bytes(&huge_count).is_err());
+    }
+
+    #[test]
+    fn cache_miss_then_hit() {
+        let (path, cache_dir) = temp_cache("hit");
+        let expected = parse_acf(&path).unwrap();
+
+        let missed = parse_acf_cached(&path, &cache_dir);
+        let cache_file = cache_path(&path, &cache_dir);
+        let written = fs::read(&cache_file);
+
+        // Plant a different tree behind the same stamp; a hit must return it without parsing
+        let mut planted = Acf::default();
+        planted.entries.push(Entry {
+            name: "Planted".into(),
+            ..Default::default()
+        });
+        if let Ok(bytes) = &written {
+            let mut contents = bytes[..STAMP_LEN].to_vec();
+            contents.extend_from_slice(&planted.to_cache_bytes());
+            fs::write(&cache_file, contents).unwrap();
+        }
+        let hit = parse_acf_cached(&path, &cache_dir);
+
+        clean_up(&path, &cache_dir);
+        assert_eq!(missed.unwrap(), expected);
+        assert!(written.is_ok());
+        assert_eq!(hit.unwrap(), planted);
+    }
+
+    #[test]
+    fn stale_source() {
+        let (path, cache_dir) = temp_cache("stale");
+        let first = parse_acf_cached(&path, &cache_dir);
+
+        let contents = fs::read_to_string(&path).unwrap().replace("\"730\"", "\"7300\"");
+        fs::write(&path, contents).unwrap();
+        let second = parse_acf_cached(&path, &cache_dir);
+
+        clean_up(&path, &cache_dir);
+        assert_eq!(first.unwrap().entries[0].expressions["appid"], "730");
+        assert_eq!(second.unwrap().entries[0].expressions["appid"], "7300");
+    }
+
+    #[test]
+    fn corrupted_cache_falls_back() {
+        let (path, cache_dir) = temp_cache("corrupted");
+        let expected = parse_acf(&path).unwrap();
+        parse_acf_cached(&path, &cache_dir).unwrap();
+
+        let cache_file = cache_path(&path, &cache_dir);
+        let mut bytes = fs::read(&cache_file).unwrap();
+        bytes.truncate(STAMP_LEN + 10);
+        bytes.extend_from_slice(&[0xFF; 16]);
+        fs::write(&cache_file, &bytes).unwrap();
+
+        let recovered = parse_acf_cached(&path, &cache_dir);
+        let rewritten = fs::read(&cache_file);
+
+        clean_up(&path, &cache_dir);
+        assert_eq!(recovered.unwrap(), expected);
+        let rewritten = rewritten.unwrap();
+        assert_eq!(Acf::from_cache_bytes(&rewritten[STAMP_LEN..]).unwrap(), expected);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::errors::*;
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::line_index::LineIndex;
+use crate::parser::{
+    check_depth, literal_parser, syntax_error, whitespace, Extra, ParseOptions, DEFAULT_MAX_DEPTH,
+    DEFAULT_QUOTE_CHAR,
+};
+use crate::span::Span;
+use chumsky::prelude::*;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// How serious a diagnostic is
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Severity {
+    /// The input can't be parsed
+    Error,
+
+    /// The input parses, but is likely not what was intended
+    Warning,
+}
+
+/// A problem found in ACF source
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// How serious the problem is
+    pub severity: Severity,
+
+    /// Location of the problem
+    pub span: Span,
+
+    /// 1-based line of the start of the span
+    pub line: usize,
+
+    /// 1-based column, in characters, of the start of the span
+    pub column: usize,
+
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Checks ACF source for problems without building a tree
+///
+/// Walks the token stream once, following the same grammar as the parser, and keeps going after
+/// each problem so all of them are reported in source order. An empty result means the source
+/// parses cleanly. Nothing is allocated for well formed input beyond a small stack of open
+/// entries, which makes this much cheaper than [`parse_acf_str`] for "check on save" style use
+///
+/// [`parse_acf_str`]: crate::parser::parse_acf_str
+pub fn check_acf(src: &str) -> Vec<Diagnostic> {
+    let mut problems = Vec::new();
+    let mut open: Vec<Frame> = Vec::new();
+    let mut pending: Option<Token> = None;
+    let mut reported_depth = false;
+
+    for token in Lexer::new(src) {
+        match token.kind {
+            TokenKind::Str => match pending.take() {
+                None => pending = Some(token),
+                Some(key) => match open.last() {
+                    Some(frame) if frame.has_children => problems.push((
+                        Span::from(key.start..token.end),
+                        "expressions must come before sub-entries".into(),
+                    )),
+                    Some(_) => {}
+                    None => problems.push((
+                        Span::from(key.start..key.end),
+                        "expected '{' after entry name".into(),
+                    )),
+                },
+            },
+            TokenKind::Open => {
+                if pending.take().is_none() {
+                    problems.push((
+                        Span::from(token.start..token.end),
+                        "expected an entry name before '{'".into(),
+                    ));
+                }
+
+                if let Some(parent) = open.last_mut() {
+                    parent.has_children = true;
+                }
+                open.push(Frame {
+                    brace: token.start,
+                    has_children: false,
+                });
+
+                if open.len() > DEFAULT_MAX_DEPTH && !reported_depth {
+                    reported_depth = true;
+                    problems.push((
+                        Span::from(token.start..token.end),
+                        format!("entries nest deeper than {} levels", DEFAULT_MAX_DEPTH),
+                    ));
+                }
+            }
+            TokenKind::Close => {
+                if let Some(key) = pending.take() {
+                    problems.push(missing_follower(&key, !open.is_empty()));
+                }
+                if open.pop().is_none() {
+                    problems.push((
+                        Span::from(token.start..token.end),
+                        "unexpected '}' without a matching '{'".into(),
+                    ));
+                }
+            }
+            TokenKind::Invalid => {
+                let message = match src[token.start..].chars().next() {
+                    Some(DEFAULT_QUOTE_CHAR) => "unterminated string".into(),
+                    Some(c) => format!("unexpected character {:?}", c),
+                    None => "unexpected end of input".into(),
+                };
+                problems.push((Span::from(token.start..token.end), message));
+            }
+        }
+    }
+
+    if let Some(key) = pending {
+        problems.push(missing_follower(&key, !open.is_empty()));
+    }
+    if let Some(frame) = open.first() {
+        problems.push((
+            Span::from(frame.brace..src.len()),
+            "expected a closing brace".into(),
+        ));
+    }
+
+    if problems.is_empty() {
+        return Vec::new();
+    }
+
+    let index = LineIndex::new(src);
+    problems
+        .into_iter()
+        .map(|(span, message)| {
+            let (line, column) = span.start_line_col(&index);
+            Diagnostic {
+                severity: Severity::Error,
+                span,
+                line,
+                column,
+                message,
+            }
+        })
+        .collect()
+}
+
+/// Sizes of an ACF document, as counted by [`count_acf`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AcfCounts {
+    /// Number of entries, including root entries
+    pub entries: usize,
+
+    /// Number of expressions
+    pub expressions: usize,
+
+    /// Deepest nesting of entries, where a root entry has a depth of 1
+    pub max_depth: usize,
+}
+
+/// Counts the entries and expressions of ACF source without building a tree
+///
+/// Runs a grammar equivalent to [`parse_acf_str`]'s that folds each entry into running counts
+/// rather than building [`Entry`] values, so no names, values, or entries are allocated. Input
+/// is accepted or rejected exactly as [`parse_acf_str`] would, with the same errors
+///
+/// [`parse_acf_str`]: crate::parser::parse_acf_str
+/// [`Entry`]: crate::parser::Entry
+pub fn count_acf(src: &str) -> Result<AcfCounts> {
+    let options = ParseOptions::default();
+    check_depth(src, DEFAULT_MAX_DEPTH, DEFAULT_QUOTE_CHAR).map_err(AcfError::Parse)?;
+
+    counts_parser()
+        .parse(src)
+        .into_result()
+        .map_err(|errs| AcfError::Parse(syntax_error(src, errs, &options)))
+}
+
+/// Counting parser
+///
+/// Matches the same input as the tree building grammar, producing the counts of all root entries
+fn counts_parser<'src>() -> impl Parser<'src, &'src str, AcfCounts, Extra<'src>> {
+    empty()
+        .to(AcfCounts::default())
+        .foldl(
+            entry_counts_parser().padded_by(whitespace()).repeated(),
+            |total, entry| AcfCounts {
+                entries: total.entries + entry.entries,
+                expressions: total.expressions + entry.expressions,
+                max_depth: total.max_depth.max(entry.max_depth),
+            },
+        )
+        .then_ignore(end())
+}
+
+/// Entry counting parser
+///
+/// Counts an entry's expressions, then folds in the counts of each of its sub-entries
+fn entry_counts_parser<'src>() -> impl Parser<'src, &'src str, AcfCounts, Extra<'src>> {
+    let string = literal_parser(DEFAULT_QUOTE_CHAR)
+        .padded_by(whitespace())
+        .ignored();
+
+    recursive(move |entry| {
+        string
+            .clone()
+            .then_ignore(just("{").padded_by(whitespace()))
+            .ignore_then(string.clone().then(string.clone()).repeated().count())
+            .map(|expressions| AcfCounts {
+                entries: 1,
+                expressions,
+                max_depth: 1,
+            })
+            .foldl(
+                entry.padded_by(whitespace()).repeated(),
+                |counts: AcfCounts, child: AcfCounts| AcfCounts {
+                    entries: counts.entries + child.entries,
+                    expressions: counts.expressions + child.expressions,
+                    max_depth: counts.max_depth.max(child.max_depth + 1),
+                },
+            )
+            .then_ignore(just("}").padded_by(whitespace()))
+            .boxed()
+    })
+}
+
+/// An entry whose closing brace hasn't been reached yet
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct Frame {
+    /// Byte offset of the entry's opening brace, where an unclosed entry's span starts to match
+    /// the parser's
+    brace: usize,
+
+    /// Whether a sub-entry has been opened within the entry
+    has_children: bool,
+}
+
+/// Describes a string left without the value or brace that should follow it
+fn missing_follower(string: &Token, in_entry: bool) -> (Span, String) {
+    let message = if in_entry {
+        "expected a value after this key"
+    } else {
+        "expected '{' after entry name"
+    };
+
+    (Span::from(string.start..string.end), message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_acf_str;
+    use std::fs;
+
+    #[test]
+    fn clean_fixture() {
+        let src = fs::read_to_string("./acfs/appmanifest_730.acf").unwrap();
+        assert!(check_acf(&src).is_empty());
+    }
+
+    #[test]
+    fn two_errors() {
+        let src = fs::read_to_string("./acfs/appmanifest_730.acf")
+            .unwrap()
+            .replace("\"StateFlags\"\t\t\"4\"", "\"StateFlags\"\t\t#\"4\"")
+            .replace(
+                "\"2279721\"\n\t}\n\t\"MountedConfig\"",
+                "\"2279721\"\n\t\t\"extra\"\n\t}\n\t\"MountedConfig\"",
+            );
+        assert!(parse_acf_str(&src).is_err());
+
+        let diagnostics = check_acf(&src);
+        assert_eq!(diagnostics.len(), 2);
+        assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+
+        assert_eq!((diagnostics[0].line, diagnostics[0].column), (7, 16));
+        assert_eq!(diagnostics[0].message, "unexpected character '#'");
+
+        assert_eq!((diagnostics[1].line, diagnostics[1].column), (73, 3));
+        assert_eq!(&src[diagnostics[1].span.range()], "\"extra\"");
+    }
+
+    #[test]
+    fn counts_match_parse() {
+        let src = fs::read_to_string("./acfs/appmanifest_730.acf").unwrap();
+        let acf = parse_acf_str(&src).unwrap();
+        let counts = count_acf(&src).unwrap();
+
+        assert_eq!(counts.entries, acf.entry_count());
+        assert_eq!(counts.expressions, acf.expression_count());
+        assert_eq!(
+            counts,
+            AcfCounts {
+                entries: 12,
+                expressions: 44,
+                max_depth: 3,
+            }
+        );
+
+        let src = "\"A\" { \"k\" \"v\" } \"B\" { \"C\" { \"D\" { } } }";
+        assert_eq!(
+            count_acf(src).unwrap(),
+            AcfCounts {
+                entries: 4,
+                expressions: 1,
+                max_depth: 3,
+            }
+        );
+        assert_eq!(count_acf("").unwrap(), AcfCounts::default());
+
+        let src = "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n";
+        assert_eq!(count_acf(src).unwrap_err(), parse_acf_str(src).unwrap_err());
+    }
+
+    #[test]
+    fn unclosed() {
+        let src = "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n";
+        let diagnostics = check_acf(src);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].span, Span::new(11, 29));
+
+        let err = parse_acf_str(src).unwrap_err();
+        assert_eq!(
+            err.as_parse_error().unwrap().span(),
+            Some(diagnostics[0].span)
+        );
+    }
+}
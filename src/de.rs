@@ -0,0 +1,362 @@
+use crate::errors::*;
+use crate::parser::Entry;
+use serde::de::{self, Deserialize, DeserializeSeed, IntoDeserializer, Visitor};
+use std::collections::HashSet;
+use std::fmt::Display;
+use std::str::FromStr;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// Deserializes a user type from an entry's contents
+///
+/// The entry behaves like a map: expressions are members holding strings, and sub-entries are
+/// members holding nested maps, so a derived struct maps its fields from keys and its nested
+/// struct (or map) fields from sub-entries. The entry's own name isn't included. Where a name
+/// repeats only the first occurrence is used, matching [`Expressions::get`]
+///
+/// Values are converted on demand: numbers are parsed from their decimal text, booleans accept
+/// `1`/`0` as well as `true`/`false`, and unit enum variants are matched by name. Names are
+/// converted the same way, so sub-entries can fill a map keyed by number (e.g. depot IDs). A
+/// value that can't be converted is reported as an invalid [`ValueError`], and other mismatches
+/// (such as a missing field) as [`AcfError::Serde`]. An entry can also be read as a sequence of
+/// its sub-entries, e.g. a `Vec` of depots, ignoring their names
+///
+/// ```ignore
+/// #[derive(serde::Deserialize)]
+/// struct App {
+///     appid: u32,
+///     name: String,
+/// }
+///
+/// let app: App = from_entry(&acf.entries[0])?;
+/// ```
+///
+/// [`Expressions::get`]: crate::expressions::Expressions::get
+pub fn from_entry<'a, T: Deserialize<'a>>(entry: &'a Entry) -> Result<T> {
+    T::deserialize(EntryDeserializer(entry))
+}
+
+impl de::Error for AcfError {
+    fn custom<T: Display>(msg: T) -> Self {
+        AcfError::Serde(msg.to_string())
+    }
+}
+
+/// Deserializer over an entry's contents
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct EntryDeserializer<'de>(&'de Entry);
+
+impl<'de> de::Deserializer<'de> for EntryDeserializer<'de> {
+    type Error = AcfError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let mut seen = HashSet::new();
+        let expressions = self.0.expressions.iter();
+        let entries = self.0.entries.iter();
+        let mut members = expressions
+            .map(|(key, value)| (key.as_str(), Member::Value(value)))
+            .chain(entries.map(|entry| (entry.name.as_str(), Member::Entry(entry))))
+            .filter(|(name, _)| seen.insert(*name))
+            .collect::<Vec<_>>();
+        members.reverse();
+
+        visitor.visit_map(Members {
+            members,
+            pending: None,
+        })
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_seq(SubEntries(self.0.entries.iter()))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct tuple tuple_struct map struct enum identifier
+    }
+}
+
+/// A member of an entry: an expression's value or a sub-entry
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+enum Member<'de> {
+    /// Value of an expression
+    Value(&'de str),
+
+    /// A sub-entry
+    Entry(&'de Entry),
+}
+
+/// Map access over an entry's members
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct Members<'de> {
+    /// Members still to visit along with their names, with the next member last
+    members: Vec<(&'de str, Member<'de>)>,
+
+    /// Member whose name was just visited
+    pending: Option<(&'de str, Member<'de>)>,
+}
+
+impl<'de> de::MapAccess<'de> for Members<'de> {
+    type Error = AcfError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        let Some((name, member)) = self.members.pop() else {
+            return Ok(None);
+        };
+        self.pending = Some((name, member));
+
+        // Names are converted like values, so they can be read as numbers
+
+        let key = ValueDeserializer {
+            key: name,
+            value: name,
+        };
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        match self.pending.take() {
+            Some((key, Member::Value(value))) => seed.deserialize(ValueDeserializer { key, value }),
+            Some((_, Member::Entry(entry))) => seed.deserialize(EntryDeserializer(entry)),
+            None => Err(de::Error::custom("value requested before its key")),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.members.len())
+    }
+}
+
+/// Sequence access over an entry's sub-entries
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct SubEntries<'de>(std::slice::Iter<'de, Entry>);
+
+impl<'de> de::SeqAccess<'de> for SubEntries<'de> {
+    type Error = AcfError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        self.0
+            .next()
+            .map(|entry| seed.deserialize(EntryDeserializer(entry)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// Deserializer over a single expression's value
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct ValueDeserializer<'de> {
+    /// Key of the expression, for error messages
+    key: &'de str,
+
+    /// Value being converted
+    value: &'de str,
+}
+
+impl ValueDeserializer<'_> {
+    /// Parses the value using its [`FromStr`] implementation
+    fn parse<T>(&self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        self.value.parse().map_err(|e: T::Err| self.invalid(e))
+    }
+
+    /// Creates an invalid value error
+    fn invalid(&self, reason: impl Display) -> AcfError {
+        AcfError::Value(ValueError::invalid(
+            self.key,
+            self.value,
+            reason.to_string(),
+        ))
+    }
+}
+
+/// Implements deserialization of numeric and character types by parsing the value
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = AcfError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.value {
+            "1" | "true" => visitor.visit_bool(true),
+            "0" | "false" => visitor.visit_bool(false),
+            _ => Err(self.invalid("expected 0, 1, true, or false")),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_borrowed_bytes(self.value.as_bytes())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(self.value.into_deserializer())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::*;
+    use serde::Deserialize;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct App {
+        #[serde(rename = "appid")]
+        app_id: u32,
+        #[serde(rename = "name")]
+        name: String,
+        state_flags: u8,
+        size_on_disk: u64,
+        allow_other_downloads_while_running: bool,
+        installed_depots: BTreeMap<u32, Depot>,
+        user_config: Option<Config>,
+        beta_key: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Depot {
+        manifest: u64,
+        size: u64,
+        dlcappid: Option<u32>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct Config {
+        language: Language,
+        #[serde(rename = "DisabledDLC")]
+        disabled_dlc: String,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Language {
+        English,
+        German,
+    }
+
+    #[test]
+    fn deserialize_struct() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let app: App = from_entry(&acf.entries[0]).unwrap();
+
+        assert_eq!(app.app_id, 730);
+        assert_eq!(app.name, "Counter-Strike 2");
+        assert_eq!(app.state_flags, 4);
+        assert_eq!(app.size_on_disk, 58882015666);
+        assert!(!app.allow_other_downloads_while_running);
+        assert_eq!(app.installed_depots.len(), 7);
+        assert_eq!(app.installed_depots[&731].manifest, 8593492943720630015);
+        assert_eq!(app.installed_depots[&731].size, 8);
+        assert_eq!(app.installed_depots[&2347779].dlcappid, Some(2279721));
+        assert_eq!(app.installed_depots[&732].dlcappid, None);
+        assert_eq!(app.beta_key, None);
+
+        let config = app.user_config.unwrap();
+        assert_eq!(config.language, Language::English);
+        assert_eq!(config.disabled_dlc, "");
+
+        let depots: Vec<Depot> = from_entry(&acf.entries[0].entries[0]).unwrap();
+        assert_eq!(depots[6].size, 1675678702);
+    }
+
+    #[test]
+    fn deserialize_errors() {
+        let acf = parse_acf_str("\"Root\" { \"manifest\" \"abc\" \"size\" \"8\" }").unwrap();
+
+        let err = from_entry::<Depot>(&acf.entries[0]).unwrap_err();
+        assert!(err.as_value_error().unwrap().is_invalid());
+        assert!(err.to_string().contains("'abc' for 'manifest'"));
+
+        let acf = parse_acf_str("\"Root\" { \"size\" \"8\" }").unwrap();
+        let err = from_entry::<Depot>(&acf.entries[0]).unwrap_err();
+        assert!(err.is_serde());
+        assert!(err.to_string().contains("manifest"));
+    }
+}
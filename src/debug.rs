@@ -0,0 +1,77 @@
+use crate::parser::{Acf, Entry};
+use std::fmt::Write;
+
+/// Spaces per level of indentation
+const INDENT: usize = 2;
+
+impl Acf {
+    /// Renders the document as an indented tree for debugging
+    ///
+    /// Each entry's name is followed by its expressions as `"key" = "value"` and then its
+    /// sub-entries, one level deeper. Strings are escaped like `{:?}`, so stray whitespace and
+    /// control characters are visible
+    pub fn pretty_debug(&self) -> String {
+        let mut out = String::new();
+        self.entries
+            .iter()
+            .for_each(|entry| write_entry(&mut out, entry, 0));
+
+        out
+    }
+}
+
+impl Entry {
+    /// Renders the entry as an indented tree for debugging, starting `indent` levels deep
+    ///
+    /// Uses the same layout as [`Acf::pretty_debug`], so a subtree can be printed on its own
+    pub fn pretty_debug(&self, indent: usize) -> String {
+        let mut out = String::new();
+        write_entry(&mut out, self, indent);
+
+        out
+    }
+}
+
+/// Writes an entry at the given indentation level
+fn write_entry(out: &mut String, entry: &Entry, indent: usize) {
+    let _ = writeln!(out, "{:width$}{:?}", "", entry.name, width = indent * INDENT);
+
+    for (key, value) in &entry.expressions {
+        let width = (indent + 1) * INDENT;
+        let _ = writeln!(out, "{:width$}{:?} = {:?}", "", key, value, width = width);
+    }
+
+    entry
+        .entries
+        .iter()
+        .for_each(|sub_entry| write_entry(out, sub_entry, indent + 1));
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::*;
+
+    #[test]
+    fn entry_pretty_debug() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let depot = &acf.entries[0].entries[0].entries[1];
+        assert_eq!(
+            depot.pretty_debug(1),
+            concat!(
+                "  \"731\"\n",
+                "    \"manifest\" = \"8593492943720630015\"\n",
+                "    \"size\" = \"8\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn acf_pretty_debug() {
+        let acf = parse_acf_str("\"Root\" { \"key\" \"a\tb\" \"Child\" { } }").unwrap();
+        assert_eq!(
+            acf.pretty_debug(),
+            "\"Root\"\n  \"key\" = \"a\\tb\"\n  \"Child\"\n"
+        );
+        assert_eq!(acf.pretty_debug(), acf.entries[0].pretty_debug(0));
+    }
+}
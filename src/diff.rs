@@ -0,0 +1,523 @@
+use crate::errors::{PatchConflict, PatchError};
+use crate::parser::{Acf, Entry};
+use crate::serializer::write_quoted;
+use std::collections::HashSet;
+
+/// A single difference between two documents
+///
+/// Paths list entry names from the root down. For expression changes the last segment is the
+/// expression's key; for entry changes it is the entry's name
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Change {
+    /// An expression was added
+    Added {
+        /// Path to the expression
+        path: Vec<String>,
+
+        /// Value of the new expression
+        value: String,
+    },
+
+    /// An expression was removed
+    Removed {
+        /// Path to the expression
+        path: Vec<String>,
+
+        /// Value of the removed expression
+        value: String,
+    },
+
+    /// An expression's value changed
+    Modified {
+        /// Path to the expression
+        path: Vec<String>,
+
+        /// Previous value
+        old: String,
+
+        /// New value
+        new: String,
+    },
+
+    /// An entry was added, along with everything inside it
+    EntryAdded {
+        /// Path to the entry
+        path: Vec<String>,
+
+        /// The new entry
+        entry: Entry,
+    },
+
+    /// An entry was removed, along with everything inside it
+    EntryRemoved {
+        /// Path to the entry
+        path: Vec<String>,
+
+        /// The removed entry
+        entry: Entry,
+    },
+}
+
+impl Change {
+    /// Returns the path of the changed expression or entry
+    pub fn path(&self) -> &[String] {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Modified { path, .. }
+            | Change::EntryAdded { path, .. }
+            | Change::EntryRemoved { path, .. } => path,
+        }
+    }
+}
+
+impl Acf {
+    /// Lists the changes that turn this document into `other`
+    ///
+    /// Entries and expressions are matched by name. Where a name repeats among siblings only its
+    /// first occurrence is compared, matching [`Expressions::get`]. Changes are listed depth
+    /// first in source order, with removals and modifications before additions at each level
+    ///
+    /// [`Expressions::get`]: crate::expressions::Expressions::get
+    pub fn diff(&self, other: &Acf) -> Vec<Change> {
+        let mut changes = Vec::new();
+        diff_entries(&self.entries, &other.entries, &mut Vec::new(), &mut changes);
+
+        changes
+    }
+
+    /// Renders the changes that turn this document into `other` as patch text for human review
+    ///
+    /// Changes are listed as by [`diff`](Acf::diff), grouped under `@@ path @@` headers naming
+    /// the entry that contains them (`@@ / @@` for root entries). Removed lines are prefixed with
+    /// `-` and added lines with `+`, followed by the expression or entry in the ACF layout. A
+    /// modified expression shows as its old line followed by its new one. In the example below, the
+    /// tabs of the ACF layout are shown as spaces
+    ///
+    /// ```text
+    /// @@ AppState @@
+    /// -    "buildid"        "20040392"
+    /// +    "buildid"        "11399846"
+    /// ```
+    pub fn diff_patch(&self, other: &Acf) -> String {
+        let changes = self.diff(other);
+        let mut out = String::new();
+        let mut current = None;
+
+        for change in &changes {
+            let Some((name, parent)) = change.path().split_last() else {
+                continue;
+            };
+            if current != Some(parent) {
+                current = Some(parent);
+                let header = if parent.is_empty() { "/".to_owned() } else { parent.join("/") };
+                out.push_str(&format!("@@ {} @@\n", header));
+            }
+
+            match change {
+                Change::Added { value, .. } => write_patch_line(&mut out, '+', name, value),
+                Change::Removed { value, .. } => write_patch_line(&mut out, '-', name, value),
+                Change::Modified { old, new, .. } => {
+                    write_patch_line(&mut out, '-', name, old);
+                    write_patch_line(&mut out, '+', name, new);
+                }
+                Change::EntryAdded { entry, .. } => write_patch_entry(&mut out, '+', entry),
+                Change::EntryRemoved { entry, .. } => write_patch_entry(&mut out, '-', entry),
+            }
+        }
+
+        out
+    }
+
+    /// Applies changes produced by [`diff`](Acf::diff), in order
+    ///
+    /// Every change must find the document in the state it expects: added items must not exist
+    /// yet, and removed or modified items must exist with the recorded old value. The first
+    /// change that doesn't is reported, and the document is left unchanged. Additions are
+    /// appended after their existing siblings
+    pub fn apply_patch(&mut self, changes: &[Change]) -> Result<(), PatchError> {
+        self.apply_changes(changes, false)
+    }
+
+    /// Applies changes regardless of the document's current state
+    ///
+    /// Additions and modifications overwrite whatever is present, creating missing parent
+    /// entries as needed, and removals of items that don't exist are skipped. Only changes with
+    /// an empty path fail, in which case the document is left unchanged
+    pub fn apply_patch_forced(&mut self, changes: &[Change]) -> Result<(), PatchError> {
+        self.apply_changes(changes, true)
+    }
+
+    /// Applies changes to a copy of the entries, keeping it only if every change succeeds
+    fn apply_changes(&mut self, changes: &[Change], force: bool) -> Result<(), PatchError> {
+        let mut entries = self.entries.clone();
+
+        for (index, change) in changes.iter().enumerate() {
+            apply_change(&mut entries, change, force).map_err(|reason| PatchError {
+                index,
+                path: change.path().to_vec(),
+                reason,
+            })?;
+        }
+
+        self.entries = entries;
+
+        Ok(())
+    }
+}
+
+impl Entry {
+    /// Compares two entries recursively, skipping expressions whose key is in `ignore_keys`
+    ///
+    /// Apart from the skipped keys, this matches `==`: names must be equal, and the remaining
+    /// expressions, their comments, and the sub-entries must match pairwise in source order. Keys
+    /// and their comments are skipped at every depth, which suits volatile values such as
+    /// `LastUpdated` or `BytesDownloaded`
+    pub fn eq_ignoring(&self, other: &Entry, ignore_keys: &[&str]) -> bool {
+        self.name == other.name
+            && self.entries.len() == other.entries.len()
+            && kept_expressions(self, ignore_keys).eq(kept_expressions(other, ignore_keys))
+            && kept_comments(self, ignore_keys).eq(kept_comments(other, ignore_keys))
+            && self
+                .entries
+                .iter()
+                .zip(&other.entries)
+                .all(|(a, b)| a.eq_ignoring(b, ignore_keys))
+    }
+}
+
+/// Iterates over the entry's expressions whose key isn't ignored
+fn kept_expressions<'a>(
+    entry: &'a Entry,
+    ignore_keys: &'a [&'a str],
+) -> impl Iterator<Item = (&'a String, &'a String)> {
+    entry
+        .expressions
+        .iter()
+        .filter(|(key, _)| !ignore_keys.contains(&key.as_str()))
+}
+
+/// Iterates over the entry's comments whose key isn't ignored
+fn kept_comments<'a>(
+    entry: &'a Entry,
+    ignore_keys: &'a [&'a str],
+) -> impl Iterator<Item = &'a (String, String)> {
+    entry
+        .comments
+        .iter()
+        .filter(|(key, _)| !ignore_keys.contains(&key.as_str()))
+}
+
+/// Records the changes between two lists of sibling entries
+fn diff_entries(
+    old: &[Entry],
+    new: &[Entry],
+    path: &mut Vec<String>,
+    changes: &mut Vec<Change>,
+) {
+    let mut seen = HashSet::new();
+    for entry in old.iter().filter(|entry| seen.insert(entry.name.as_str())) {
+        path.push(entry.name.clone());
+        match new.iter().find(|new_entry| new_entry.name == entry.name) {
+            Some(new_entry) => diff_entry(entry, new_entry, path, changes),
+            None => changes.push(Change::EntryRemoved {
+                path: path.clone(),
+                entry: entry.clone(),
+            }),
+        }
+        path.pop();
+    }
+
+    let mut seen = HashSet::new();
+    for entry in new.iter().filter(|entry| seen.insert(entry.name.as_str())) {
+        if !old.iter().any(|old_entry| old_entry.name == entry.name) {
+            changes.push(Change::EntryAdded {
+                path: child_path(path, &entry.name),
+                entry: entry.clone(),
+            });
+        }
+    }
+}
+
+/// Records the changes between two versions of an entry found at `path`
+fn diff_entry(old: &Entry, new: &Entry, path: &mut Vec<String>, changes: &mut Vec<Change>) {
+    let mut seen = HashSet::new();
+    for (key, value) in old.expressions.iter().filter(|(key, _)| seen.insert(key.as_str())) {
+        match new.expressions.get(key) {
+            Some(new_value) if new_value != value => changes.push(Change::Modified {
+                path: child_path(path, key),
+                old: value.clone(),
+                new: new_value.clone(),
+            }),
+            Some(_) => {}
+            None => changes.push(Change::Removed {
+                path: child_path(path, key),
+                value: value.clone(),
+            }),
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for (key, value) in new.expressions.iter().filter(|(key, _)| seen.insert(key.as_str())) {
+        if !old.expressions.contains_key(key) {
+            changes.push(Change::Added {
+                path: child_path(path, key),
+                value: value.clone(),
+            });
+        }
+    }
+
+    diff_entries(&old.entries, &new.entries, path, changes);
+}
+
+/// Writes an expression as a line of patch text
+fn write_patch_line(out: &mut String, sign: char, key: &str, value: &str) {
+    out.push(sign);
+    out.push('\t');
+    write_quoted(out, key);
+    out.push_str("\t\t");
+    write_quoted(out, value);
+    out.push('\n');
+}
+
+/// Writes an entry, including its sub-entries, as lines of patch text
+fn write_patch_entry(out: &mut String, sign: char, entry: &Entry) {
+    for line in entry.to_vdf_string().lines() {
+        out.push(sign);
+        out.push('\t');
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+/// Returns `path` extended by one segment
+fn child_path(path: &[String], name: &str) -> Vec<String> {
+    let mut path = path.to_vec();
+    path.push(name.to_owned());
+
+    path
+}
+
+/// Applies a single change to the list of root entries
+fn apply_change(
+    entries: &mut Vec<Entry>,
+    change: &Change,
+    force: bool,
+) -> Result<(), PatchConflict> {
+    let (name, parent) = change.path().split_last().ok_or(PatchConflict::InvalidPath)?;
+
+    match change {
+        Change::Added { value, .. } => {
+            let entry = expression_parent(entries, parent, force)?;
+            if !force && entry.expressions.contains_key(name) {
+                return Err(PatchConflict::AlreadyExists);
+            }
+            entry.expressions.insert(name.as_str(), value.as_str());
+        }
+        Change::Removed { value, .. } => {
+            let entry = match expression_parent(entries, parent, false) {
+                Ok(entry) => entry,
+                Err(_) if force => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            match entry.expressions.get(name) {
+                Some(found) if !force && found != value => {
+                    return Err(PatchConflict::ValueMismatch {
+                        expected: value.clone(),
+                        found: found.clone(),
+                    });
+                }
+                None if !force => return Err(PatchConflict::MissingTarget),
+                _ => {
+                    entry.expressions.remove(name);
+                }
+            }
+        }
+        Change::Modified { old, new, .. } => {
+            let entry = expression_parent(entries, parent, force)?;
+            match entry.expressions.get(name) {
+                Some(found) if !force && found != old => {
+                    return Err(PatchConflict::ValueMismatch {
+                        expected: old.clone(),
+                        found: found.clone(),
+                    });
+                }
+                None if !force => return Err(PatchConflict::MissingTarget),
+                _ => {
+                    entry.expressions.insert(name.as_str(), new.as_str());
+                }
+            }
+        }
+        Change::EntryAdded { entry, .. } => {
+            let siblings = entry_list(entries, parent, force)?;
+            match siblings.iter().position(|sibling| sibling.name == *name) {
+                Some(index) if force => siblings[index] = entry.clone(),
+                Some(_) => return Err(PatchConflict::AlreadyExists),
+                None => siblings.push(entry.clone()),
+            }
+        }
+        Change::EntryRemoved { entry, .. } => {
+            let siblings = match entry_list(entries, parent, false) {
+                Ok(siblings) => siblings,
+                Err(_) if force => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            match siblings.iter().position(|sibling| sibling.name == *name) {
+                Some(index) if !force && siblings[index] != *entry => {
+                    return Err(PatchConflict::EntryMismatch);
+                }
+                Some(index) => {
+                    siblings.remove(index);
+                }
+                None if !force => return Err(PatchConflict::MissingTarget),
+                None => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the entry holding an expression, given the path of entry names leading to it
+fn expression_parent<'a>(
+    entries: &'a mut Vec<Entry>,
+    path: &[String],
+    create: bool,
+) -> Result<&'a mut Entry, PatchConflict> {
+    let (name, parent) = path.split_last().ok_or(PatchConflict::InvalidPath)?;
+    let siblings = entry_list(entries, parent, create)?;
+
+    let index = match siblings.iter().position(|sibling| sibling.name == *name) {
+        Some(index) => index,
+        None if create => {
+            siblings.push(Entry {
+                name: name.clone(),
+                ..Default::default()
+            });
+            siblings.len() - 1
+        }
+        None => return Err(PatchConflict::MissingParent),
+    };
+
+    Ok(&mut siblings[index])
+}
+
+/// Returns the sub-entries of the entry at `path`, or the root entries if the path is empty
+fn entry_list<'a>(
+    entries: &'a mut Vec<Entry>,
+    path: &[String],
+    create: bool,
+) -> Result<&'a mut Vec<Entry>, PatchConflict> {
+    if path.is_empty() {
+        Ok(entries)
+    } else {
+        expression_parent(entries, path, create).map(|entry| &mut entry.entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::*;
+
+    #[test]
+    fn apply_diff() {
+        let old = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let new = parse_acf("./acfs/appmanifest_745.acf").unwrap();
+
+        let changes = old.diff(&new);
+        assert!(changes.contains(&Change::Modified {
+            path: vec!["AppState".into(), "buildid".into()],
+            old: "20040392".into(),
+            new: "11399846".into(),
+        }));
+        assert!(old.diff(&old).is_empty());
+
+        let mut patched = old.clone();
+        patched.apply_patch(&changes).unwrap();
+        assert_eq!(patched, new);
+    }
+
+    #[test]
+    fn diff_patch() {
+        let old = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let new = parse_acf("./acfs/appmanifest_745.acf").unwrap();
+
+        let patch = old.diff_patch(&new);
+        assert!(patch.starts_with("@@ AppState @@\n"));
+        assert!(patch.contains("-\t\"buildid\"\t\t\"20040392\"\n+\t\"buildid\"\t\t\"11399846\"\n"));
+        assert!(patch.contains("@@ AppState/InstalledDepots @@\n"));
+        assert!(patch.contains("-\t\"731\"\n-\t{\n"));
+        assert!(old.diff_patch(&old).is_empty());
+
+        let mut extra = old.clone();
+        extra.entries.push(Entry {
+            name: "Extra".into(),
+            ..Default::default()
+        });
+        assert_eq!(old.diff_patch(&extra), "@@ / @@\n+\t\"Extra\"\n+\t{\n+\t}\n");
+    }
+
+    #[test]
+    fn eq_ignoring() {
+        let old = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let mut new = old.clone();
+        let root_entry = &mut new.entries[0];
+        root_entry.expressions.insert("LastUpdated", "1700000000");
+        root_entry.expressions.insert("SizeOnDisk", "0");
+        root_entry.entries[0].entries[1].expressions.insert("size", "0");
+
+        let ignored = ["LastUpdated", "SizeOnDisk", "size"];
+        assert!(old.entries[0].eq_ignoring(&new.entries[0], &ignored));
+        assert!(!old.entries[0].eq_ignoring(&new.entries[0], &ignored[..2]));
+        assert_ne!(old, new);
+
+        let mut commented = new.clone();
+        let root_entry = &mut commented.entries[0];
+        root_entry.comments.push(("LastUpdated".to_owned(), "volatile".to_owned()));
+        assert!(root_entry.eq_ignoring(&new.entries[0], &ignored));
+        root_entry.comments.push(("appid".to_owned(), "the app's ID".to_owned()));
+        assert!(!root_entry.eq_ignoring(&new.entries[0], &ignored));
+
+        new.entries[0].entries[0].entries.pop();
+        assert!(!old.entries[0].eq_ignoring(&new.entries[0], &ignored));
+    }
+
+    #[test]
+    fn conflict() {
+        let old = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let new = parse_acf("./acfs/appmanifest_745.acf").unwrap();
+        let changes = old.diff(&new);
+
+        let mut other = parse_acf("./acfs/appmanifest_228980.acf").unwrap();
+        let original = other.clone();
+        let err = other.apply_patch(&changes).unwrap_err();
+        assert_eq!(err.index, 0);
+        assert_eq!(err.path, ["AppState", "appid"]);
+        assert_eq!(
+            err.reason,
+            PatchConflict::ValueMismatch {
+                expected: "730".into(),
+                found: "228980".into(),
+            }
+        );
+        assert_eq!(other, original);
+
+        other.apply_patch_forced(&changes).unwrap();
+        assert_eq!(other.entries[0].expressions["appid"], "745");
+        let depots = &other.entries[0].entries[0];
+        assert_eq!(depots.entries.last(), new.entries[0].entries[0].entries.last());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip() {
+        let old = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let new = parse_acf("./acfs/appmanifest_745.acf").unwrap();
+        let changes = old.diff(&new);
+
+        let json = serde_json::to_string(&changes).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Change>>(&json).unwrap(), changes);
+    }
+}
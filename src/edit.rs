@@ -0,0 +1,1257 @@
+use crate::errors::PathError;
+use crate::parser::{Acf, Entry};
+use std::collections::hash_map::Entry as MapEntry;
+use std::collections::{HashMap, HashSet};
+use std::mem;
+
+/// Which of several same-named entries survives deduplication
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DedupKeep {
+    /// Keep the first entry with each name
+    #[default]
+    First,
+
+    /// Keep the last entry with each name
+    Last,
+}
+
+/// How conflicting values are resolved when merging entries
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum MergeStrategy {
+    /// Values from the entry being merged in replace existing ones
+    #[default]
+    Overwrite,
+
+    /// Existing values are kept; only keys that are missing are added
+    KeepExisting,
+}
+
+/// How entry names and expression keys are rewritten by [`Acf::normalize_keys`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum KeyCase {
+    /// Converts every name and key to lowercase
+    Lower,
+
+    /// Leaves names and keys as they are
+    #[default]
+    Preserve,
+
+    /// Maps known appmanifest names and keys to Valve's spelling, ignoring case, and leaves any
+    /// others as they are
+    Canonical,
+}
+
+/// Valve's spelling of the names and keys found in appmanifest files
+const CANONICAL_KEYS: &[&str] = &[
+    "AllowOtherDownloadsWhileRunning",
+    "AppState",
+    "AutoUpdateBehavior",
+    "BetaKey",
+    "BytesDownloaded",
+    "BytesStaged",
+    "BytesToDownload",
+    "BytesToStage",
+    "DisabledDLC",
+    "DownloadType",
+    "FullValidateAfterNextUpdate",
+    "InstallScripts",
+    "InstalledDepots",
+    "LastOwner",
+    "LastPlayed",
+    "LauncherPath",
+    "MountedConfig",
+    "ScheduledAutoUpdate",
+    "SharedDepots",
+    "SizeOnDisk",
+    "StagedDepots",
+    "StagingSize",
+    "StateFlags",
+    "TargetBuildID",
+    "UpdateResult",
+    "UserConfig",
+    "appid",
+    "buildid",
+    "dlcappid",
+    "installdir",
+    "language",
+    "lastupdated",
+    "manifest",
+    "name",
+    "optionaldlc",
+    "size",
+    "universe",
+];
+
+/// Options for [`Acf::prune_empty_with`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct PruneOptions {
+    /// Also removes expressions whose value is the empty string, before checking whether their
+    /// entry is empty
+    pub drop_empty_values: bool,
+}
+
+impl Acf {
+    /// Moves all of `other`'s root entries onto the end of this document
+    ///
+    /// No attempt is made to reconcile entries sharing a name; both are kept
+    pub fn append(&mut self, other: Acf) {
+        self.entries.extend(other.entries);
+    }
+
+    /// Consumes the document, returning its root entries without cloning them
+    pub fn into_entries(self) -> Vec<Entry> {
+        self.entries
+    }
+
+    /// Renames the document's root entry, returning `false` and leaving the document untouched
+    /// unless it has exactly one root entry
+    pub fn rename_root(&mut self, new_name: &str) -> bool {
+        match self.entries.as_mut_slice() {
+            [root_entry] => {
+                root_entry.name = new_name.to_owned();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Collapses same-named entries at every level of the tree
+    ///
+    /// Among siblings sharing a name, only the first or last (per `keep`) survives, along with
+    /// its whole subtree. Survivors keep their relative order. Deduplication then continues
+    /// within each surviving entry
+    pub fn dedup_entries(&mut self, keep: DedupKeep) {
+        dedup_entries(&mut self.entries, keep);
+    }
+
+    /// Prunes the document down to a single slash separated path (e.g.
+    /// `AppState/InstalledDepots/731/size`)
+    ///
+    /// The first segment names a root entry and later segments name sub-entries, taking the first
+    /// match at every level. Entries along the path lose their other expressions and sub-entries.
+    /// If the last segment names an expression only that expression is kept; if it names an
+    /// entry, that entry is kept whole. If the path doesn't exist, every entry is removed
+    pub fn retain_path(&mut self, path: &str) {
+        let segments = path.split('/').collect::<Vec<_>>();
+        let entries = std::mem::take(&mut self.entries);
+
+        if let Some(mut entry) = entries.into_iter().find(|entry| entry.name == segments[0]) {
+            if retain_path(&mut entry, &segments[1..]) {
+                self.entries.push(entry);
+            }
+        }
+    }
+
+    /// Rewrites every entry name and expression key in the given style, returning the slash
+    /// separated paths of any collisions this creates
+    ///
+    /// A collision happens when siblings whose names differ only by case normalize to the same
+    /// name; only the first of them survives. Siblings that already shared an exact name are left
+    /// alone, as they weren't created by normalization
+    pub fn normalize_keys(&mut self, style: KeyCase) -> Vec<String> {
+        self.normalize_keys_with(style, DedupKeep::First)
+    }
+
+    /// Rewrites every entry name and expression key as in [`Acf::normalize_keys`], keeping the
+    /// first or last of each set of colliding siblings per `keep`
+    pub fn normalize_keys_with(&mut self, style: KeyCase, keep: DedupKeep) -> Vec<String> {
+        let mut collisions = Vec::new();
+        if style != KeyCase::Preserve {
+            normalize_entries(&mut self.entries, style, keep, "", &mut collisions);
+        }
+
+        collisions
+    }
+
+    /// Returns a copy of the document with every expression key renamed by `f`, leaving this one
+    /// untouched
+    ///
+    /// Keys keep their positions and entry names are not renamed, as with
+    /// [`Acf::rename_all_keys`]. Nothing is dropped, so keys that `f` maps to the same name are
+    /// all kept
+    pub fn with_mapped_keys(&self, f: impl Fn(&str) -> String) -> Acf {
+        let entries = self.entries.iter().map(|entry| mapped_keys(entry, &f));
+        Acf {
+            entries: entries.collect(),
+        }
+    }
+
+    /// Renames every expression key in the tree with `f`, returning the slash separated paths of
+    /// any collisions this creates
+    ///
+    /// Keys keep their positions. If renaming makes sibling keys that used to differ equal, the
+    /// first of them is kept and the rest are dropped. Keys that were already repeated beforehand
+    /// are left alone. Entry names are not renamed
+    pub fn rename_all_keys(&mut self, f: impl Fn(&str) -> String) -> Vec<String> {
+        let mut collisions = Vec::new();
+        for entry in &mut self.entries {
+            let prefix = format!("{}/", entry.name);
+            rename_keys(entry, &f, &prefix, &mut collisions);
+        }
+
+        collisions
+    }
+
+    /// Normalizes the document for comparison and storage
+    ///
+    /// At every level of the tree, values are trimmed of surrounding whitespace and repeated keys
+    /// are collapsed to their last value. Expressions are then sorted by key and sub-entries by
+    /// name, keeping same-named entries in their original order. Finally empty entries are
+    /// removed as by [`Acf::prune_empty`]. Documents that differ only in layout, ordering, or
+    /// these redundancies canonicalize to equal trees
+    pub fn canonicalize(&mut self) {
+        canonicalize(&mut self.entries);
+        self.prune_empty();
+    }
+
+    /// Removes every entry with no expressions and no sub-entries, returning how many were removed
+    ///
+    /// Pruning runs bottom-up, so an entry whose sub-entries were all pruned is removed as well.
+    /// Root entries are pruned the same way
+    pub fn prune_empty(&mut self) -> usize {
+        self.prune_empty_with(PruneOptions::default())
+    }
+
+    /// Removes every empty entry as in [`Acf::prune_empty`], applying the given options
+    ///
+    /// The returned count only includes entries, not dropped expressions
+    pub fn prune_empty_with(&mut self, options: PruneOptions) -> usize {
+        prune_empty(&mut self.entries, options)
+    }
+
+    /// Removes every expression whose key isn't in `keep`, at every level of the tree, then
+    /// removes the entries left empty as by [`Acf::prune_empty`]
+    ///
+    /// Keys are compared exactly
+    pub fn prune_to_keys(&mut self, keep: &[&str]) {
+        prune_to_keys(&mut self.entries, keep);
+        self.prune_empty();
+    }
+
+    /// Sorts the root entries, and the sub-entries of every entry in the tree, by name
+    ///
+    /// Sorting is stable, so same-named entries keep their relative order. Expressions aren't
+    /// reordered
+    pub fn sort_all_children(&mut self) {
+        sort_children(&mut self.entries);
+    }
+
+    /// Replaces every occurrence of `from` with `to` within the values of the whole tree, e.g. to
+    /// move stored paths to another drive, returning the number of occurrences replaced
+    ///
+    /// Keys and entry names are left untouched. Occurrences don't overlap, as with
+    /// [`str::replace`], and an empty `from` matches nothing
+    pub fn replace_in_values(&mut self, from: &str, to: &str) -> usize {
+        if from.is_empty() {
+            return 0;
+        }
+
+        replace_in_values(&mut self.entries, from, to)
+    }
+
+    /// Removes the entry at `path` from the document, returning it as the sole root of a new
+    /// document
+    ///
+    /// The first segment names a root entry and later segments name sub-entries, taking the first
+    /// match at every level. Fails without changing the document if nothing exists at the path,
+    /// or if its last segment names an expression
+    pub fn split_off(&mut self, path: &[&str]) -> Result<Acf, PathError> {
+        let (&name, parents) = path.split_last().ok_or(PathError::Empty)?;
+
+        let siblings = match parents.split_first() {
+            None => &mut self.entries,
+            Some((&root, rest)) => {
+                let mut parent = self
+                    .entries
+                    .iter_mut()
+                    .find(|entry| entry.name == root)
+                    .ok_or_else(|| not_found(path))?;
+                for &segment in rest {
+                    parent = parent
+                        .entries
+                        .iter_mut()
+                        .find(|sub_entry| sub_entry.name == segment)
+                        .ok_or_else(|| not_found(path))?;
+                }
+
+                if !parent.entries.iter().any(|sub_entry| sub_entry.name == name)
+                    && parent.expressions.get(name).is_some()
+                {
+                    return Err(PathError::NotAnEntry(path.join("/")));
+                }
+                &mut parent.entries
+            }
+        };
+
+        let index = siblings
+            .iter()
+            .position(|entry| entry.name == name)
+            .ok_or_else(|| not_found(path))?;
+
+        Ok(Acf {
+            entries: vec![siblings.remove(index)],
+        })
+    }
+
+    /// Copies the entry at `path` into a new document as its sole root, leaving this one intact
+    ///
+    /// Paths are resolved as in [`Acf::split_off`]
+    pub fn extract(&self, path: &[&str]) -> Result<Acf, PathError> {
+        let (&root, rest) = path.split_first().ok_or(PathError::Empty)?;
+
+        let mut entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == root)
+            .ok_or_else(|| not_found(path))?;
+        for (depth, &segment) in rest.iter().enumerate() {
+            entry = match entry.entries.iter().find(|sub_entry| sub_entry.name == segment) {
+                Some(sub_entry) => sub_entry,
+                None if depth + 1 == rest.len() && entry.expressions.get(segment).is_some() => {
+                    return Err(PathError::NotAnEntry(path.join("/")));
+                }
+                None => return Err(not_found(path)),
+            };
+        }
+
+        Ok(Acf {
+            entries: vec![entry.clone()],
+        })
+    }
+}
+
+impl Entry {
+    /// Returns the first sub-entry with the given name, appending an empty one if there is none
+    pub fn get_or_insert_child(&mut self, name: &str) -> &mut Entry {
+        let index = match self.entries.iter().position(|sub_entry| sub_entry.name == name) {
+            Some(index) => index,
+            None => {
+                self.entries.push(Entry {
+                    name: name.to_owned(),
+                    ..Default::default()
+                });
+                self.entries.len() - 1
+            }
+        };
+
+        &mut self.entries[index]
+    }
+
+    /// Replaces the sub-entry at `index` with `child`, returning the sub-entry it replaced
+    ///
+    /// Returns `None` and leaves the entry untouched if `index` is out of bounds, e.g. after the
+    /// sub-entries shifted since the index was found with [`Entry::child_index`]
+    pub fn replace_child(&mut self, index: usize, child: Entry) -> Option<Entry> {
+        let slot = self.entries.get_mut(index)?;
+        Some(mem::replace(slot, child))
+    }
+
+    /// Sorts the entry's immediate sub-entries by name, leaving their own sub-entries as they are
+    ///
+    /// Sorting is stable, so same-named sub-entries keep their relative order
+    pub fn sort_children_by_name(&mut self) {
+        self.entries.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    /// Makes sure the first expression with the given key holds `value`, returning whether the
+    /// entry changed
+    ///
+    /// A differing value is replaced in place, and a missing key is appended, as with
+    /// [`Expressions::insert`]. An expression already holding `value` is left untouched
+    ///
+    /// [`Expressions::insert`]: crate::expressions::Expressions::insert
+    pub fn ensure(&mut self, key: &str, value: &str) -> bool {
+        if self.expressions.get(key).map(String::as_str) == Some(value) {
+            return false;
+        }
+
+        self.expressions.insert(key, value);
+        true
+    }
+
+    /// Replaces every expression with the given key by one expression per value, in order
+    ///
+    /// The new expressions take the place of the first existing one, or are appended if the key
+    /// doesn't exist yet. An empty `values` removes the key entirely, along with its comment
+    pub fn set_all(&mut self, key: &str, values: impl IntoIterator<Item = String>) {
+        let mut values = Some(values);
+        let mut items = Vec::with_capacity(self.expressions.len());
+
+        for (name, value) in mem::take(&mut self.expressions) {
+            if name != key {
+                items.push((name, value));
+            } else if let Some(values) = values.take() {
+                items.extend(values.into_iter().map(|value| (key.to_owned(), value)));
+            }
+        }
+        if let Some(values) = values {
+            items.extend(values.into_iter().map(|value| (key.to_owned(), value)));
+        }
+
+        self.expressions = items.into_iter().collect();
+        if !self.expressions.contains_key(key) {
+            self.comments.retain(|(name, _)| name != key);
+        }
+    }
+
+    /// Renames every expression key in the entry and its sub-entries with `f`, returning the
+    /// paths (relative to this entry) of any collisions this creates
+    ///
+    /// Collisions are handled as in [`Acf::rename_all_keys`]
+    pub fn rename_all_keys(&mut self, f: impl Fn(&str) -> String) -> Vec<String> {
+        let mut collisions = Vec::new();
+        rename_keys(self, &f, "", &mut collisions);
+
+        collisions
+    }
+
+    /// Folds another entry's expressions and sub-entries into this one, returning the slash
+    /// separated paths (relative to this entry) of every key whose values conflicted
+    ///
+    /// Expressions and sub-entries are paired by name and occurrence: the second `"key"` in
+    /// `other` is compared with the second `"key"` here, and the second `"Child"` merges into the
+    /// second `"Child"`, recursively. Paired keys with different values are resolved by
+    /// `strategy`. Anything without a counterpart is appended, so repeated names are never
+    /// collapsed. Comments in `other` are copied over for keys that don't have one here
+    pub fn merge_from(&mut self, other: &Entry, strategy: MergeStrategy) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        merge_from(self, other, strategy, "", &mut conflicts);
+
+        conflicts
+    }
+}
+
+/// Merges `other` into `entry`, recording conflicting keys under the given path prefix
+fn merge_from(
+    entry: &mut Entry,
+    other: &Entry,
+    strategy: MergeStrategy,
+    prefix: &str,
+    conflicts: &mut Vec<String>,
+) {
+    let mut items = mem::take(&mut entry.expressions).into_iter().collect::<Vec<_>>();
+    let mut seen = Vec::<&str>::new();
+    for (key, value) in &other.expressions {
+        let occurrence = seen.iter().filter(|&&seen_key| seen_key == key).count();
+        seen.push(key);
+
+        let existing = items
+            .iter_mut()
+            .filter(|(existing_key, _)| existing_key == key)
+            .nth(occurrence);
+        match existing {
+            None => items.push((key.clone(), value.clone())),
+            Some((_, existing)) if existing == value => {}
+            Some((_, existing)) => {
+                conflicts.push(format!("{}{}", prefix, key));
+                if strategy == MergeStrategy::Overwrite {
+                    existing.clone_from(value);
+                }
+            }
+        }
+    }
+    entry.expressions = items.into_iter().collect();
+
+    for (key, comment) in &other.comments {
+        if entry.comment_for(key).is_none() {
+            entry.comments.push((key.clone(), comment.clone()));
+        }
+    }
+
+    let mut seen = Vec::<&str>::new();
+    for sub_entry in &other.entries {
+        let occurrence = seen.iter().filter(|&&name| name == sub_entry.name).count();
+        seen.push(&sub_entry.name);
+
+        let target = entry
+            .entries
+            .iter_mut()
+            .filter(|existing| existing.name == sub_entry.name)
+            .nth(occurrence);
+        match target {
+            Some(target) => {
+                let prefix = format!("{}{}/", prefix, sub_entry.name);
+                merge_from(target, sub_entry, strategy, &prefix, conflicts);
+            }
+            None => entry.entries.push(sub_entry.clone()),
+        }
+    }
+}
+
+/// Creates an error for a path that doesn't exist
+fn not_found(path: &[&str]) -> PathError {
+    PathError::NotFound(path.join("/"))
+}
+
+/// Recursively collapses same-named entries in the list
+fn dedup_entries(entries: &mut Vec<Entry>, keep: DedupKeep) {
+    if keep == DedupKeep::Last {
+        entries.reverse();
+    }
+
+    let mut seen = HashSet::new();
+    entries.retain(|entry| seen.insert(entry.name.clone()));
+
+    if keep == DedupKeep::Last {
+        entries.reverse();
+    }
+
+    entries
+        .iter_mut()
+        .for_each(|entry| dedup_entries(&mut entry.entries, keep));
+}
+
+/// Recursively trims values, collapses repeated keys to their last value, and sorts the entries
+fn canonicalize(entries: &mut [Entry]) {
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for entry in entries {
+        let mut seen = HashSet::new();
+        let mut expressions = mem::take(&mut entry.expressions)
+            .into_iter()
+            .rev()
+            .filter(|(key, _)| seen.insert(key.clone()))
+            .map(|(key, value)| {
+                let trimmed = value.trim();
+                let value = if trimmed.len() == value.len() { value } else { trimmed.to_owned() };
+                (key, value)
+            })
+            .collect::<Vec<_>>();
+        expressions.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entry.expressions = expressions.into_iter().collect();
+
+        canonicalize(&mut entry.entries);
+    }
+}
+
+/// Rewrites a name or key in the given style
+fn normalize_key(key: &str, style: KeyCase) -> String {
+    match style {
+        KeyCase::Lower => key.to_lowercase(),
+        KeyCase::Preserve => key.to_owned(),
+        KeyCase::Canonical => CANONICAL_KEYS
+            .iter()
+            .find(|canonical| canonical.eq_ignore_ascii_case(key))
+            .map_or_else(|| key.to_owned(), |canonical| (*canonical).to_owned()),
+    }
+}
+
+/// Recursively normalizes the names of the entries in the list, and the keys of their expressions
+fn normalize_entries(
+    entries: &mut Vec<Entry>,
+    style: KeyCase,
+    keep: DedupKeep,
+    prefix: &str,
+    collisions: &mut Vec<String>,
+) {
+    let named = mem::take(entries)
+        .into_iter()
+        .map(|entry| (entry.name.clone(), normalize_key(&entry.name, style), entry))
+        .collect();
+    *entries = resolve_collisions(named, keep, prefix, collisions)
+        .into_iter()
+        .map(|(name, entry)| Entry { name, ..entry })
+        .collect();
+
+    for entry in entries.iter_mut() {
+        let path = format!("{}{}/", prefix, entry.name);
+        rename_expressions(entry, &|key| normalize_key(key, style), keep, &path, collisions);
+        normalize_entries(&mut entry.entries, style, keep, &path, collisions);
+    }
+}
+
+/// Recursively renames the keys of an entry's expressions, recording collisions under the given
+/// path prefix
+fn rename_keys(
+    entry: &mut Entry,
+    f: &impl Fn(&str) -> String,
+    prefix: &str,
+    collisions: &mut Vec<String>,
+) {
+    rename_expressions(entry, f, DedupKeep::First, prefix, collisions);
+    for sub_entry in &mut entry.entries {
+        let prefix = format!("{}{}/", prefix, sub_entry.name);
+        rename_keys(sub_entry, f, &prefix, collisions);
+    }
+}
+
+/// Renames the keys of an entry's own expressions with `f`, resolving collisions per `keep`
+///
+/// Comments follow their keys, and comments on keys dropped as collisions are dropped with them
+fn rename_expressions(
+    entry: &mut Entry,
+    f: &impl Fn(&str) -> String,
+    keep: DedupKeep,
+    prefix: &str,
+    collisions: &mut Vec<String>,
+) {
+    let keyed = mem::take(&mut entry.expressions)
+        .into_iter()
+        .map(|(key, value)| {
+            let renamed = f(&key);
+            (key.clone(), renamed, (key, value))
+        })
+        .collect();
+    let survivors = resolve_collisions(keyed, keep, prefix, collisions);
+
+    let renamed = survivors
+        .iter()
+        .map(|(renamed, (original, _))| (original.as_str(), renamed.as_str()))
+        .collect::<HashMap<_, _>>();
+    entry.comments = mem::take(&mut entry.comments)
+        .into_iter()
+        .filter_map(|(key, comment)| Some((renamed.get(key.as_str())?.to_string(), comment)))
+        .collect();
+
+    entry.expressions = survivors
+        .into_iter()
+        .map(|(renamed, (_, value))| (renamed, value))
+        .collect();
+}
+
+/// Copies an entry and its sub-entries, renaming every expression key with `f`
+fn mapped_keys(entry: &Entry, f: &impl Fn(&str) -> String) -> Entry {
+    let expressions = entry.expressions.iter().map(|(key, value)| (f(key), value.clone()));
+    let entries = entry.entries.iter().map(|sub_entry| mapped_keys(sub_entry, f));
+    let comments = entry.comments.iter().map(|(key, comment)| (f(key), comment.clone()));
+
+    Entry {
+        name: entry.name.clone(),
+        expressions: expressions.collect(),
+        entries: entries.collect(),
+        comments: comments.collect(),
+    }
+}
+
+/// Drops all but one of each set of items whose distinct original names normalized to the same
+/// name, recording the name under the given path prefix
+///
+/// Items are `(original, normalized, value)` triples; survivors are returned under their
+/// normalized names
+fn resolve_collisions<T>(
+    items: Vec<(String, String, T)>,
+    keep: DedupKeep,
+    prefix: &str,
+    collisions: &mut Vec<String>,
+) -> Vec<(String, T)> {
+    // Normalized name -> (first index, last index, whether the originals differ)
+    let mut groups = HashMap::<&str, (usize, usize, bool)>::new();
+    for (index, (original, normalized, _)) in items.iter().enumerate() {
+        match groups.entry(normalized.as_str()) {
+            MapEntry::Vacant(group) => {
+                group.insert((index, index, false));
+            }
+            MapEntry::Occupied(mut group) => {
+                let (first, last, collided) = group.get_mut();
+                *last = index;
+                *collided |= items[*first].0 != *original;
+            }
+        }
+    }
+
+    let survivors = items
+        .iter()
+        .enumerate()
+        .map(|(index, (_, normalized, _))| {
+            let (first, last, collided) = groups[normalized.as_str()];
+            if collided && index == first {
+                collisions.push(format!("{}{}", prefix, normalized));
+            }
+
+            let survivor = if keep == DedupKeep::First { first } else { last };
+            !collided || index == survivor
+        })
+        .collect::<Vec<_>>();
+
+    items
+        .into_iter()
+        .zip(survivors)
+        .filter(|(_, survives)| *survives)
+        .map(|((_, normalized, value), _)| (normalized, value))
+        .collect()
+}
+
+/// Recursively removes empty entries from the list, returning how many were removed
+fn prune_empty(entries: &mut Vec<Entry>, options: PruneOptions) -> usize {
+    let mut removed = 0;
+    for entry in entries.iter_mut() {
+        if options.drop_empty_values {
+            entry.expressions.retain(|_, value| !value.is_empty());
+        }
+        removed += prune_empty(&mut entry.entries, options);
+    }
+
+    let before = entries.len();
+    entries.retain(|entry| !entry.expressions.is_empty() || !entry.entries.is_empty());
+
+    removed + before - entries.len()
+}
+
+/// Recursively removes the expressions whose key isn't listed
+fn prune_to_keys(entries: &mut [Entry], keep: &[&str]) {
+    for entry in entries {
+        entry.expressions.retain(|key, _| keep.contains(&key));
+        prune_to_keys(&mut entry.entries, keep);
+    }
+}
+
+/// Sorts entries by name, then recurses into each one's sub-entries
+fn sort_children(entries: &mut [Entry]) {
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    entries
+        .iter_mut()
+        .for_each(|entry| sort_children(&mut entry.entries));
+}
+
+/// Recursively replaces `from` with `to` within every value, counting the occurrences
+fn replace_in_values(entries: &mut [Entry], from: &str, to: &str) -> usize {
+    let mut count = 0;
+    for entry in entries {
+        entry.expressions = mem::take(&mut entry.expressions)
+            .into_iter()
+            .map(|(key, value)| match value.matches(from).count() {
+                0 => (key, value),
+                matches => {
+                    count += matches;
+                    (key, value.replace(from, to))
+                }
+            })
+            .collect();
+        count += replace_in_values(&mut entry.entries, from, to);
+    }
+
+    count
+}
+
+/// Prunes an entry down to the path below it, returning `false` if the path doesn't exist
+fn retain_path(entry: &mut Entry, path: &[&str]) -> bool {
+    let Some((&segment, rest)) = path.split_first() else {
+        return true;
+    };
+
+    if rest.is_empty() {
+        if let Some(value) = entry.expressions.get(segment).cloned() {
+            entry.expressions = [(segment.to_owned(), value)].into_iter().collect();
+            entry.entries.clear();
+            return true;
+        }
+    }
+
+    let Some(index) = entry.entries.iter().position(|sub_entry| sub_entry.name == segment) else {
+        return false;
+    };
+
+    let mut sub_entry = entry.entries.swap_remove(index);
+    if !retain_path(&mut sub_entry, rest) {
+        return false;
+    }
+
+    entry.expressions.clear();
+    entry.entries = vec![sub_entry];
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::*;
+
+    #[test]
+    fn into_entries() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let expected = acf.entries.clone();
+
+        let mut entries = acf.into_entries();
+        assert_eq!(entries, expected);
+
+        let root_entry = entries.remove(0);
+        let depots = root_entry.entries.into_iter().next().unwrap();
+        assert_eq!(depots.name, "InstalledDepots");
+        assert!(entries.is_empty());
+    }
+
+    #[test]
+    fn append() {
+        let mut acf = parse_acf_str("\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n}\n").unwrap();
+        let other = parse_acf_str("\"AppState\"\n{\n\t\"appid\"\t\t\"745\"\n}\n").unwrap();
+
+        acf.append(other);
+        assert_eq!(acf.entries.len(), 2);
+        assert_eq!(
+            acf.to_vdf_string(),
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n}\n\"AppState\"\n{\n\t\"appid\"\t\t\"745\"\n}\n"
+        );
+    }
+
+    #[test]
+    fn rename_root() {
+        let mut acf = parse_acf_str("\"Root\"\n{\n\t\"appid\"\t\t\"730\"\n}\n").unwrap();
+
+        assert!(acf.rename_root("AppState"));
+        assert_eq!(acf.to_vdf_string(), "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n}\n");
+
+        acf.append(acf.clone());
+        assert!(!acf.rename_root("Other"));
+        assert!(!Acf::default().rename_root("Other"));
+        assert_eq!(acf.entries[0].name, "AppState");
+    }
+
+    #[test]
+    fn dedup_keep_first() {
+        let mut acf = parse_acf("./acfs/duplicate_sections.acf").unwrap();
+        acf.dedup_entries(DedupKeep::First);
+
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.child_names(), ["UserConfig", "MountedConfig"]);
+        assert_eq!(root_entry.entries[0].expressions["language"], "english");
+    }
+
+    #[test]
+    fn dedup_keep_last() {
+        let mut acf = parse_acf("./acfs/duplicate_sections.acf").unwrap();
+        acf.dedup_entries(DedupKeep::Last);
+
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.child_names(), ["MountedConfig", "UserConfig"]);
+        assert_eq!(root_entry.entries[1].expressions["language"], "german");
+    }
+
+    #[test]
+    fn retain_path() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        acf.retain_path("AppState/InstalledDepots/2347779/dlcappid");
+        assert_eq!(
+            acf.to_vdf_string(),
+            concat!(
+                "\"AppState\"\n{\n",
+                "\t\"InstalledDepots\"\n\t{\n",
+                "\t\t\"2347779\"\n\t\t{\n",
+                "\t\t\t\"dlcappid\"\t\t\"2279721\"\n",
+                "\t\t}\n\t}\n}\n",
+            )
+        );
+
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let user_config = acf.entries[0].entries[2].clone();
+        acf.retain_path("AppState/UserConfig");
+        assert!(acf.entries[0].expressions.is_empty());
+        assert_eq!(acf.entries[0].entries, [user_config]);
+
+        acf.retain_path("AppState/Missing/key");
+        assert!(acf.entries.is_empty());
+    }
+
+    #[test]
+    fn split_off() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let depots = acf.entries[0].entries[0].clone();
+
+        let split = acf.split_off(&["AppState", "InstalledDepots"]).unwrap();
+        assert_eq!(split.entries, [depots]);
+        assert_eq!(
+            acf.entries[0].child_names(),
+            ["SharedDepots", "UserConfig", "MountedConfig"]
+        );
+
+        assert_eq!(parse_acf_str(&split.to_vdf_string()).unwrap(), split);
+        assert_eq!(parse_acf_str(&acf.to_vdf_string()).unwrap(), acf);
+
+        let err = acf.split_off(&["AppState", "InstalledDepots"]).unwrap_err();
+        assert_eq!(err, PathError::NotFound("AppState/InstalledDepots".into()));
+        assert!(acf.split_off(&["AppState", "appid"]).unwrap_err().is_not_an_entry());
+        assert!(acf.split_off(&[]).unwrap_err().is_empty());
+
+        let root = acf.split_off(&["AppState"]).unwrap();
+        assert_eq!(root.entries[0].expressions["appid"], "730");
+        assert!(acf.entries.is_empty());
+    }
+
+    #[test]
+    fn extract() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+
+        let extracted = acf.extract(&["AppState", "InstalledDepots", "731"]).unwrap();
+        assert_eq!(extracted.entries[0].expressions["size"], "8");
+        assert_eq!(acf.entries[0].entries[0].entries[1], extracted.entries[0]);
+
+        let err = acf.extract(&["AppState", "UserConfig", "language"]).unwrap_err();
+        assert_eq!(err, PathError::NotAnEntry("AppState/UserConfig/language".into()));
+        assert!(acf.extract(&["AppState", "Missing", "x"]).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn get_or_insert_child() {
+        let mut entry = parse_acf_str("\"AppState\" { \"appid\" \"730\" \"Other\" { } }")
+            .unwrap()
+            .entries
+            .remove(0);
+
+        let user_config = entry.get_or_insert_child("UserConfig");
+        assert!(user_config.expressions.is_empty());
+        user_config.expressions.insert("language", "english");
+        assert_eq!(entry.child_names(), ["Other", "UserConfig"]);
+
+        let user_config = entry.get_or_insert_child("UserConfig");
+        assert_eq!(user_config.expressions["language"], "english");
+        assert_eq!(entry.entries.len(), 2);
+    }
+
+    #[test]
+    fn replace_child() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let root_entry = &mut acf.entries[0];
+        let index = root_entry.child_index("UserConfig").unwrap();
+        let child = parse_acf_str("\"UserConfig\" { \"language\" \"german\" }").unwrap();
+
+        let replaced = root_entry.replace_child(index, child.into_entries().remove(0));
+        assert_eq!(replaced.unwrap().expressions["language"], "english");
+        assert_eq!(root_entry.entries[index].expressions.len(), 1);
+        assert!(root_entry.replace_child(10, Entry::default()).is_none());
+
+        let out = acf.to_vdf_string();
+        assert!(out.contains("\t\"UserConfig\"\n\t{\n\t\t\"language\"\t\t\"german\"\n\t}\n"));
+        assert_eq!(parse_acf_str(&out).unwrap(), acf);
+    }
+
+    #[test]
+    fn sort_children() {
+        let src = concat!(
+            "\"Root\" { \"key\" \"value\" ",
+            "\"b\" { \"z\" { } \"y\" { \"k\" \"1\" } } ",
+            "\"a\" { } ",
+            "\"b\" { \"k\" \"2\" } }",
+        );
+        let mut acf = parse_acf_str(src).unwrap();
+
+        let mut root_entry = acf.entries[0].clone();
+        root_entry.sort_children_by_name();
+        assert_eq!(root_entry.child_names(), ["a", "b", "b"]);
+        assert_eq!(root_entry.entries[1].child_names(), ["z", "y"]);
+        assert_eq!(root_entry.entries[2].expressions["k"], "2");
+
+        acf.sort_all_children();
+        assert_eq!(
+            acf.to_vdf_string(),
+            concat!(
+                "\"Root\"\n{\n",
+                "\t\"key\"\t\t\"value\"\n",
+                "\t\"a\"\n\t{\n\t}\n",
+                "\t\"b\"\n\t{\n",
+                "\t\t\"y\"\n\t\t{\n\t\t\t\"k\"\t\t\"1\"\n\t\t}\n",
+                "\t\t\"z\"\n\t\t{\n\t\t}\n",
+                "\t}\n",
+                "\t\"b\"\n\t{\n\t\t\"k\"\t\t\"2\"\n\t}\n",
+                "}\n",
+            )
+        );
+    }
+
+    #[test]
+    fn ensure() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let root_entry = &mut acf.entries[0];
+        let before = root_entry.key_order().len();
+
+        assert!(root_entry.ensure("BetaKey", "public"));
+        assert!(!root_entry.ensure("BetaKey", "public"));
+        assert_eq!(root_entry.expressions["BetaKey"], "public");
+        assert_eq!(root_entry.key_order().len(), before + 1);
+
+        assert!(!root_entry.ensure("appid", "730"));
+        assert!(root_entry.ensure("StateFlags", "6"));
+        assert_eq!(root_entry.expressions["StateFlags"], "6");
+        assert_eq!(root_entry.key_order()[4], "StateFlags");
+    }
+
+    #[test]
+    fn set_all() {
+        let src = "\"Root\" { \"tag\" \"a\" \"name\" \"x\" \"tag\" \"b\" \"tag\" \"c\" }";
+        let mut entry = parse_acf_str(src).unwrap().entries.remove(0);
+
+        entry.set_all("tag", ["d".to_owned(), "e".to_owned()]);
+        let items = entry
+            .expressions
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(items, [("tag", "d"), ("tag", "e"), ("name", "x")]);
+
+        entry.set_all("other", vec!["f".to_owned()]);
+        assert_eq!(entry.expressions["other"], "f");
+        assert_eq!(entry.expressions.len(), 4);
+
+        entry.set_comment("tag", "kept while the key exists");
+        entry.set_all("tag", vec!["g".to_owned()]);
+        assert_eq!(entry.comment_for("tag"), Some("kept while the key exists"));
+
+        entry.set_all("tag", Vec::new());
+        assert!(!entry.expressions.contains_key("tag"));
+        assert_eq!(entry.expressions.len(), 2);
+        assert_eq!(entry.comment_for("tag"), None);
+    }
+
+    #[test]
+    fn rename_all_keys() {
+        let original = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let mut acf = original.clone();
+        assert!(acf.rename_all_keys(str::to_lowercase).is_empty());
+
+        let root_entry = &acf.entries[0];
+        let keys = root_entry.expressions.keys().collect::<Vec<_>>();
+        let expected = original.entries[0]
+            .expressions
+            .keys()
+            .map(|key| key.to_lowercase())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, expected.iter().collect::<Vec<_>>());
+        assert_eq!(root_entry.expressions["stateflags"], "4");
+        assert_eq!(root_entry.entries[2].expressions["disableddlc"], "");
+        assert_eq!(root_entry.child_names(), original.entries[0].child_names());
+
+        let src = "\"Root\" { \"Key\" \"a\" \"KEY\" \"b\" \"Child\" { \"x\" \"1\" \"X\" \"2\" } }";
+        let mut entry = parse_acf_str(src).unwrap().entries.remove(0);
+        let collisions = entry.rename_all_keys(|key| key.to_lowercase());
+        assert_eq!(collisions, ["key", "Child/x"]);
+        assert_eq!(entry.expressions["key"], "a");
+        assert_eq!(entry.entries[0].expressions.len(), 1);
+    }
+
+    #[test]
+    fn with_mapped_keys() {
+        let original = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let snapshot = original.clone();
+        let upper = original.with_mapped_keys(str::to_uppercase);
+        assert_eq!(original, snapshot);
+
+        let root_entry = &upper.entries[0];
+        assert_eq!(root_entry.name, "AppState");
+        assert_eq!(root_entry.expressions["APPID"], "730");
+        assert_eq!(root_entry.entries[0].entries[1].expressions["SIZE"], "8");
+        assert_eq!(root_entry.child_names(), original.entries[0].child_names());
+        assert_eq!(upper.leaf_paths().len(), original.leaf_paths().len());
+        assert!(!original.entries[0].expressions.contains_key("APPID"));
+
+        let acf = parse_acf_str("\"Root\" { \"Key\" \"a\" \"KEY\" \"b\" }").unwrap();
+        assert_eq!(acf.with_mapped_keys(str::to_lowercase).entries[0].expressions.len(), 2);
+    }
+
+    #[test]
+    fn canonicalize() {
+        let src = concat!(
+            "\"AppState\"\n{\n\t\"name\"\t\t\"Counter-Strike 2\"\n\t\"appid\"\t\t\"730\"\n",
+            "\t\"UserConfig\"\n\t{\n\t\t\"language\"\t\t\"english\"\n\t}\n",
+            "\t\"InstalledDepots\"\n\t{\n\t\t\"731\"\n\t\t{\n",
+            "\t\t\t\"size\"\t\t\"8\"\n\t\t}\n\t}\n}\n",
+        );
+        let other = concat!(
+            "\"AppState\" { \"appid\" \"745\" \"name\" \"Counter-Strike 2\t\" \"appid\" \"730\" ",
+            "\"InstalledDepots\" { \"731\" { \"size\" \" 8 \" } \"732\" { } } ",
+            "\"MountedConfig\" { } \"UserConfig\" { \"language\" \"english\" } }",
+        );
+
+        let mut acf = parse_acf_str(src).unwrap();
+        let mut other = parse_acf_str(other).unwrap();
+        assert_ne!(acf, other);
+
+        acf.canonicalize();
+        other.canonicalize();
+        assert_eq!(acf, other);
+
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.expressions.keys().collect::<Vec<_>>(), ["appid", "name"]);
+        assert_eq!(root_entry.child_names(), ["InstalledDepots", "UserConfig"]);
+        assert_eq!(other.entries[0].expressions["appid"], "730");
+        assert_eq!(other.entries[0].entries[0].entries[0].expressions["size"], "8");
+    }
+
+    #[test]
+    fn merge_from_overwrite() {
+        let mut entry = parse_acf("./acfs/merge_live.acf").unwrap().entries.remove(0);
+        let backup = parse_acf("./acfs/merge_backup.acf").unwrap();
+        let conflicts = entry.merge_from(&backup.entries[0], MergeStrategy::Overwrite);
+        assert_eq!(conflicts, ["BytesDownloaded", "UserConfig/language"]);
+
+        assert_eq!(entry.expressions["BytesDownloaded"], "50");
+        assert_eq!(entry.expressions["buildid"], "1");
+        assert_eq!(entry.child_names(), ["UserConfig", "UserConfig"]);
+        assert_eq!(entry.entries[0].expressions["language"], "german");
+        assert_eq!(entry.entries[0].expressions["betakey"], "beta");
+        assert_eq!(entry.entries[1].expressions["language"], "french");
+    }
+
+    #[test]
+    fn merge_from_keep_existing() {
+        let mut entry = parse_acf("./acfs/merge_live.acf").unwrap().entries.remove(0);
+        let backup = parse_acf("./acfs/merge_backup.acf").unwrap();
+        let conflicts = entry.merge_from(&backup.entries[0], MergeStrategy::KeepExisting);
+        assert_eq!(conflicts, ["BytesDownloaded", "UserConfig/language"]);
+
+        assert_eq!(entry.expressions["BytesDownloaded"], "100");
+        assert_eq!(entry.expressions["buildid"], "1");
+        assert_eq!(entry.entries[0].expressions["language"], "english");
+        assert_eq!(entry.entries[0].expressions["betakey"], "beta");
+        assert_eq!(entry.entries.len(), 2);
+
+        assert!(entry.clone().merge_from(&entry, MergeStrategy::Overwrite).is_empty());
+
+        let mut other = backup.entries[0].clone();
+        other.set_comment("buildid", "from the backup");
+        other.set_comment("BytesDownloaded", "ignored");
+        entry.set_comment("BytesDownloaded", "live");
+        entry.merge_from(&other, MergeStrategy::KeepExisting);
+        assert_eq!(entry.comment_for("buildid"), Some("from the backup"));
+        assert_eq!(entry.comment_for("BytesDownloaded"), Some("live"));
+    }
+
+    #[test]
+    fn merge_from_repeated_keys() {
+        let src = r#""AppState" { "appid" "730" "tag" "a" }"#;
+        let mut entry = parse_acf_str(src).unwrap().entries.remove(0);
+        let src = r#""AppState" { "tag" "a" "tag" "b" "lang" "en" "lang" "de" }"#;
+        let other = parse_acf_str(src).unwrap().entries.remove(0);
+
+        let conflicts = entry.merge_from(&other, MergeStrategy::Overwrite);
+        assert!(conflicts.is_empty());
+        assert_eq!(entry.key_order(), ["appid", "tag", "tag", "lang", "lang"]);
+        assert_eq!(entry.expressions.values().collect::<Vec<_>>(), ["730", "a", "b", "en", "de"]);
+
+        let src = r#""AppState" { "tag" "a" "tag" "c" }"#;
+        let other = parse_acf_str(src).unwrap().entries.remove(0);
+        assert_eq!(entry.merge_from(&other, MergeStrategy::KeepExisting), ["tag"]);
+        assert_eq!(entry.expressions.values().nth(2).unwrap(), "b");
+        assert_eq!(entry.merge_from(&other, MergeStrategy::Overwrite), ["tag"]);
+        assert_eq!(entry.expressions.values().nth(2).unwrap(), "c");
+        assert_eq!(entry.expressions["tag"], "a");
+    }
+
+    #[test]
+    fn prune_empty() {
+        let src = concat!(
+            "\"AppState\" { \"appid\" \"730\" ",
+            "\"Outer\" { \"Inner\" { \"Leaf\" { } } \"Sibling\" { } } ",
+            "\"UserConfig\" { \"DisabledDLC\" \"\" } } ",
+            "\"Empty\" { }",
+        );
+        let mut acf = parse_acf_str(src).unwrap();
+        assert_eq!(acf.prune_empty(), 5);
+        assert_eq!(acf.entries.len(), 1);
+        assert_eq!(acf.entries[0].child_names(), ["UserConfig"]);
+
+        let options = PruneOptions {
+            drop_empty_values: true,
+        };
+        assert_eq!(acf.prune_empty_with(options), 1);
+        assert!(acf.entries[0].entries.is_empty());
+        assert_eq!(acf.entries[0].expressions["appid"], "730");
+        assert_eq!(acf.prune_empty(), 0);
+    }
+
+    #[test]
+    fn replace_in_values() {
+        let src = concat!(
+            r#""libraryfolders" { "0" { "path" "C:\Program Files (x86)\Steam" } "#,
+            r#""1" { "path" "D:\SteamLibrary" "backup" "D:\Backup\D:\Old" } }"#,
+        );
+        let mut acf = parse_acf_str(src).unwrap();
+
+        assert_eq!(acf.replace_in_values(r"D:\", r"E:\"), 3);
+        let library = &acf.entries[0].entries[1];
+        assert_eq!(library.expressions["path"], r"E:\SteamLibrary");
+        assert_eq!(library.expressions["backup"], r"E:\Backup\E:\Old");
+        let path = &acf.entries[0].entries[0].expressions["path"];
+        assert_eq!(path, r"C:\Program Files (x86)\Steam");
+
+        assert_eq!(acf.replace_in_values(r"D:\", r"E:\"), 0);
+        assert_eq!(acf.replace_in_values("", "x"), 0);
+        assert_eq!(acf.replace_in_values("0", "1"), 0);
+    }
+
+    #[test]
+    fn prune_to_keys() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        acf.prune_to_keys(&["appid", "name", "installdir"]);
+
+        assert_eq!(
+            acf.to_vdf_string(),
+            concat!(
+                "\"AppState\"\n{\n",
+                "\t\"appid\"\t\t\"730\"\n",
+                "\t\"name\"\t\t\"Counter-Strike 2\"\n",
+                "\t\"installdir\"\t\t\"Counter-Strike Global Offensive\"\n",
+                "}\n",
+            )
+        );
+
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        acf.prune_to_keys(&["size"]);
+        assert_eq!(acf.entries[0].child_names(), ["InstalledDepots"]);
+        assert_eq!(acf.leaf_paths().len(), 7);
+
+        acf.prune_to_keys(&[]);
+        assert!(acf.entries.is_empty());
+    }
+
+    #[test]
+    fn normalize_keys_canonical() {
+        let mut acf = parse_acf("./acfs/mixed_case.acf").unwrap();
+        let collisions = acf.normalize_keys(KeyCase::Canonical);
+        assert_eq!(collisions, ["AppState/StateFlags", "AppState/UserConfig"]);
+
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.name, "AppState");
+        assert_eq!(root_entry.expressions["appid"], "730");
+        assert_eq!(root_entry.expressions["StateFlags"], "4");
+        assert_eq!(root_entry.expressions["Custom"], "x");
+        assert_eq!(root_entry.child_names(), ["UserConfig"]);
+        assert_eq!(root_entry.entries[0].expressions["language"], "english");
+
+        let mut acf = parse_acf("./acfs/mixed_case.acf").unwrap();
+        acf.normalize_keys_with(KeyCase::Canonical, DedupKeep::Last);
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.expressions["StateFlags"], "6");
+        assert_eq!(root_entry.entries[0].expressions["BetaKey"], "beta");
+    }
+
+    #[test]
+    fn normalize_keys_lower_and_preserve() {
+        let mut acf = parse_acf("./acfs/mixed_case.acf").unwrap();
+        let collisions = acf.normalize_keys(KeyCase::Lower);
+        assert_eq!(collisions, ["appstate/stateflags", "appstate/userconfig"]);
+        assert_eq!(acf.entries[0].expressions["custom"], "x");
+
+        let mut acf = parse_acf("./acfs/mixed_case.acf").unwrap();
+        assert!(acf.normalize_keys(KeyCase::Preserve).is_empty());
+        assert_eq!(acf, parse_acf("./acfs/mixed_case.acf").unwrap());
+
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        assert!(acf.normalize_keys(KeyCase::Canonical).is_empty());
+        assert_eq!(acf, parse_acf("./acfs/appmanifest_730.acf").unwrap());
+    }
+
+    #[test]
+    fn renaming_keys_moves_comments() {
+        let src = concat!(
+            "\"AppState\" {\n\t\"APPID\" \"730\" // the app's ID\n",
+            "\t\"stateflags\" \"4\"\n\t\"StateFlags\" \"6\" // dropped\n}\n",
+        );
+        let options = ParseOptions {
+            capture_comments: true,
+            ..Default::default()
+        };
+        let parsed = parse_acf_str_with_options(src, &options).unwrap();
+
+        let mut acf = parsed.clone();
+        acf.normalize_keys(KeyCase::Canonical);
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.comment_for("appid"), Some("the app's ID"));
+        assert_eq!(root_entry.comment_for("APPID"), None);
+        assert_eq!(root_entry.comment_for("StateFlags"), None);
+
+        let mut acf = parsed;
+        acf.rename_all_keys(str::to_uppercase);
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.comment_for("APPID"), Some("the app's ID"));
+        assert_eq!(root_entry.comment_for("STATEFLAGS"), None);
+    }
+}
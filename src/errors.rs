@@ -1,26 +1,135 @@
-use chumsky::prelude::SimpleSpan;
+use crate::span::Span;
 use std::error;
 use std::fmt;
+use std::io;
+use std::path::PathBuf;
 
 /// Top level error type
+///
+/// New variants may be added in future releases. Prefer the `is_*` predicates over exhaustive
+/// matching
 #[derive(Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
 pub enum AcfError {
     /// An error occurred reading a file
     Read(String),
 
+    /// The file to read doesn't exist
+    NotFound(PathBuf),
+
+    /// An error occurred writing a file
+    Write(String),
+
     /// An error occurring during parsing (with specific sub-type)
     Parse(ParseError),
 
+    /// An error reading a typed value from an entry (with specific sub-type)
+    Value(ValueError),
+
+    /// An error converting between a document and a user type with `serde`
+    Serde(String),
+
     /// An unknown/uncategorized error
     #[default]
     Unknown,
 }
 
+impl AcfError {
+    /// Creates a read error for the given path
+    pub fn read(path: impl Into<String>) -> Self {
+        AcfError::Read(path.into())
+    }
+
+    /// Creates a not found error for the given path
+    pub fn not_found(path: impl Into<PathBuf>) -> Self {
+        AcfError::NotFound(path.into())
+    }
+
+    /// Creates the error for a failure reading the given path, which is [`AcfError::NotFound`]
+    /// when the file doesn't exist and [`AcfError::Read`] otherwise
+    pub(crate) fn reading(path: &str, err: &io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => AcfError::NotFound(path.into()),
+            _ => AcfError::Read(path.into()),
+        }
+    }
+
+    /// Creates a write error for the given path
+    pub fn write(path: impl Into<String>) -> Self {
+        AcfError::Write(path.into())
+    }
+
+    /// Creates a parse error wrapping the given [`ParseError`]
+    pub fn parse(err: ParseError) -> Self {
+        AcfError::Parse(err)
+    }
+
+    /// Creates a value error wrapping the given [`ValueError`]
+    pub fn value(err: ValueError) -> Self {
+        AcfError::Value(err)
+    }
+
+    /// Returns `true` if the error occurred reading a file
+    pub fn is_read(&self) -> bool {
+        matches!(self, AcfError::Read(..))
+    }
+
+    /// Returns `true` if the file to read doesn't exist
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, AcfError::NotFound(..))
+    }
+
+    /// Returns `true` if the error occurred writing a file
+    pub fn is_write(&self) -> bool {
+        matches!(self, AcfError::Write(..))
+    }
+
+    /// Returns `true` if the error occurred during parsing
+    pub fn is_parse(&self) -> bool {
+        matches!(self, AcfError::Parse(..))
+    }
+
+    /// Returns `true` if the error occurred reading a typed value
+    pub fn is_value(&self) -> bool {
+        matches!(self, AcfError::Value(..))
+    }
+
+    /// Returns `true` if the error occurred converting to or from a user type
+    pub fn is_serde(&self) -> bool {
+        matches!(self, AcfError::Serde(..))
+    }
+
+    /// Returns `true` if the error is unknown/uncategorized
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, AcfError::Unknown)
+    }
+
+    /// Returns the underlying [`ParseError`], if any
+    pub fn as_parse_error(&self) -> Option<&ParseError> {
+        match self {
+            AcfError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// Returns the underlying [`ValueError`], if any
+    pub fn as_value_error(&self) -> Option<&ValueError> {
+        match self {
+            AcfError::Value(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for AcfError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             AcfError::Read(val) => write!(f, "failed to read '{}'", &val),
+            AcfError::NotFound(path) => write!(f, "'{}' does not exist", path.display()),
+            AcfError::Write(val) => write!(f, "failed to write '{}'", &val),
             AcfError::Parse(..) => write!(f, "the provided input could not be parsed"),
+            AcfError::Value(e) => write!(f, "{}", e),
+            AcfError::Serde(msg) => write!(f, "{}", msg),
             AcfError::Unknown => write!(f, "an unknown error occurred"),
         }
     }
@@ -30,7 +139,11 @@ impl error::Error for AcfError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
             AcfError::Read(..) => None,
+            AcfError::NotFound(..) => None,
+            AcfError::Write(..) => None,
             AcfError::Parse(ref e) => Some(e),
+            AcfError::Value(ref e) => Some(e),
+            AcfError::Serde(..) => None,
             AcfError::Unknown => None,
         }
     }
@@ -61,22 +174,120 @@ impl error::Error for IOError {
 }
 
 /// Representation of a parser error
+///
+/// New variants may be added in future releases. Prefer the `is_*` predicates over exhaustive
+/// matching
 #[derive(Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
 pub enum ParseError {
     /// A closing brace was not found
-    ExpectedClosingBrace(SimpleSpan),
+    ExpectedClosingBrace(Span),
+
+    /// A resource limit set in the parse options was exceeded
+    LimitExceeded {
+        /// The limit that was exceeded
+        which: Limit,
+
+        /// The configured value of the limit
+        limit: usize,
+
+        /// Location of the item that exceeded the limit
+        span: Span,
+    },
+
+    /// The input didn't match the grammar
+    Syntax {
+        /// Location of the unexpected input
+        span: Span,
+
+        /// Description of what was found and expected
+        message: String,
+    },
+
+    /// A key was repeated within an entry while parsing with [`DuplicatePolicy::Error`]
+    ///
+    /// [`DuplicatePolicy::Error`]: crate::parser::DuplicatePolicy::Error
+    DuplicateKey(Span),
 
     /// An unknown/uncategorized error
     #[default]
     Unknown,
 }
 
+impl ParseError {
+    /// Creates an error for a missing closing brace within the given span
+    pub fn expected_closing_brace(span: Span) -> Self {
+        ParseError::ExpectedClosingBrace(span)
+    }
+
+    /// Creates an error for an exceeded resource limit
+    pub fn limit_exceeded(which: Limit, limit: usize, span: Span) -> Self {
+        ParseError::LimitExceeded { which, limit, span }
+    }
+
+    /// Creates an error for input that didn't match the grammar
+    pub fn syntax(span: Span, message: impl Into<String>) -> Self {
+        ParseError::Syntax {
+            span,
+            message: message.into(),
+        }
+    }
+
+    /// Creates an error for a repeated key, spanning its expression
+    pub fn duplicate_key(span: Span) -> Self {
+        ParseError::DuplicateKey(span)
+    }
+
+    /// Returns the location of the error within the input, if known
+    pub fn span(&self) -> Option<Span> {
+        match *self {
+            ParseError::ExpectedClosingBrace(span) => Some(span),
+            ParseError::LimitExceeded { span, .. } => Some(span),
+            ParseError::Syntax { span, .. } => Some(span),
+            ParseError::DuplicateKey(span) => Some(span),
+            ParseError::Unknown => None,
+        }
+    }
+
+    /// Returns `true` if a closing brace was not found
+    pub fn is_expected_closing_brace(&self) -> bool {
+        matches!(self, ParseError::ExpectedClosingBrace(..))
+    }
+
+    /// Returns `true` if a resource limit was exceeded
+    pub fn is_limit_exceeded(&self) -> bool {
+        matches!(self, ParseError::LimitExceeded { .. })
+    }
+
+    /// Returns `true` if the input didn't match the grammar
+    pub fn is_syntax(&self) -> bool {
+        matches!(self, ParseError::Syntax { .. })
+    }
+
+    /// Returns `true` if a key was repeated within an entry
+    pub fn is_duplicate_key(&self) -> bool {
+        matches!(self, ParseError::DuplicateKey(..))
+    }
+
+    /// Returns `true` if the error is unknown/uncategorized
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, ParseError::Unknown)
+    }
+}
+
 impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             ParseError::ExpectedClosingBrace(val) => {
                 write!(f, "expected a closing brace within '{}'", &val)
             }
+            ParseError::LimitExceeded { which, limit, span } => {
+                write!(f, "exceeded the {} limit of {} at '{}'", which, limit, span)
+            }
+            ParseError::Syntax { span, ref message } => {
+                write!(f, "syntax error at '{}': {}", span, message)
+            }
+            ParseError::DuplicateKey(val) => write!(f, "duplicate key at '{}'", &val),
             ParseError::Unknown => write!(f, "an unknown parsing error occurred"),
         }
     }
@@ -86,7 +297,339 @@ impl error::Error for ParseError {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match *self {
             ParseError::ExpectedClosingBrace(_) => None,
+            ParseError::LimitExceeded { .. } => None,
+            ParseError::Syntax { .. } => None,
+            ParseError::DuplicateKey(_) => None,
             ParseError::Unknown => None,
         }
     }
 }
+
+/// Representation of an error reading a typed value from an entry
+///
+/// New variants may be added in future releases. Prefer the `is_*` predicates over exhaustive
+/// matching
+#[derive(Debug, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum ValueError {
+    /// No expression with the given key exists
+    Missing(String),
+
+    /// The value couldn't be interpreted as the requested type
+    Invalid {
+        /// Key of the expression
+        key: String,
+
+        /// Value that failed to convert
+        value: String,
+
+        /// Why the conversion failed
+        reason: String,
+    },
+
+    /// An unknown/uncategorized error
+    #[default]
+    Unknown,
+}
+
+impl ValueError {
+    /// Creates an error for a missing key
+    pub fn missing(key: impl Into<String>) -> Self {
+        ValueError::Missing(key.into())
+    }
+
+    /// Creates an error for a value that couldn't be converted
+    pub fn invalid(
+        key: impl Into<String>,
+        value: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        ValueError::Invalid {
+            key: key.into(),
+            value: value.into(),
+            reason: reason.into(),
+        }
+    }
+
+    /// Returns `true` if the key was missing
+    pub fn is_missing(&self) -> bool {
+        matches!(self, ValueError::Missing(..))
+    }
+
+    /// Returns `true` if the value couldn't be converted
+    pub fn is_invalid(&self) -> bool {
+        matches!(self, ValueError::Invalid { .. })
+    }
+
+    /// Returns `true` if the error is unknown/uncategorized
+    pub fn is_unknown(&self) -> bool {
+        matches!(self, ValueError::Unknown)
+    }
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValueError::Missing(key) => write!(f, "no value found for '{}'", key),
+            ValueError::Invalid { key, value, reason } => {
+                write!(f, "invalid value '{}' for '{}': {}", value, key, reason)
+            }
+            ValueError::Unknown => write!(f, "an unknown value error occurred"),
+        }
+    }
+}
+
+impl error::Error for ValueError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            ValueError::Missing(..) => None,
+            ValueError::Invalid { .. } => None,
+            ValueError::Unknown => None,
+        }
+    }
+}
+
+/// A resource limit that can be set in the parse options
+///
+/// New limits may be added in future releases, so matches on this need a wildcard arm
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Limit {
+    /// Maximum number of expressions across the whole document
+    TotalKeys,
+
+    /// Maximum number of entries across the whole document
+    TotalEntries,
+
+    /// Maximum length of a single string
+    ValueLength,
+
+    /// Maximum nesting depth of entries
+    Depth,
+
+    /// Maximum number of expressions directly within a single entry
+    ExpressionsPerEntry,
+
+    /// Maximum size of the input in bytes
+    InputBytes,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Limit::TotalKeys => write!(f, "total keys"),
+            Limit::TotalEntries => write!(f, "total entries"),
+            Limit::ValueLength => write!(f, "value length"),
+            Limit::Depth => write!(f, "nesting depth"),
+            Limit::ExpressionsPerEntry => write!(f, "expressions per entry"),
+            Limit::InputBytes => write!(f, "input size"),
+        }
+    }
+}
+
+/// Representation of a change that couldn't be applied as part of a patch
+#[derive(Debug, PartialEq, Eq)]
+pub struct PatchError {
+    /// Position of the change within the patch
+    pub index: usize,
+
+    /// Path of the change that couldn't be applied, as returned by [`Change::path`]
+    ///
+    /// [`Change::path`]: crate::diff::Change::path
+    pub path: Vec<String>,
+
+    /// Why the change couldn't be applied
+    pub reason: PatchConflict,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "failed to apply change {} at '{}': {}",
+            self.index,
+            self.path.join("/"),
+            self.reason
+        )
+    }
+}
+
+impl error::Error for PatchError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/// Reason a change in a patch couldn't be applied
+///
+/// New variants may be added in future releases. Prefer matching with a wildcard arm
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PatchConflict {
+    /// The change's path was empty
+    InvalidPath,
+
+    /// An entry along the change's path doesn't exist
+    MissingParent,
+
+    /// The expression or entry to remove or modify doesn't exist
+    MissingTarget,
+
+    /// The expression or entry to add already exists
+    AlreadyExists,
+
+    /// The expression's current value isn't the one the change expects
+    ValueMismatch {
+        /// Value recorded in the change
+        expected: String,
+
+        /// Value found in the document
+        found: String,
+    },
+
+    /// The entry to remove differs from the one recorded in the change
+    EntryMismatch,
+}
+
+impl fmt::Display for PatchConflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchConflict::InvalidPath => write!(f, "the path is empty"),
+            PatchConflict::MissingParent => write!(f, "a parent entry doesn't exist"),
+            PatchConflict::MissingTarget => write!(f, "the target doesn't exist"),
+            PatchConflict::AlreadyExists => write!(f, "the target already exists"),
+            PatchConflict::ValueMismatch { expected, found } => {
+                write!(f, "expected value '{}' but found '{}'", expected, found)
+            }
+            PatchConflict::EntryMismatch => write!(f, "the entry differs from the one recorded"),
+        }
+    }
+}
+
+/// Representation of a string that can't be written without quotes, as requested by a
+/// [`PrettyConfig`]
+///
+/// [`PrettyConfig`]: crate::serializer::PrettyConfig
+#[derive(Debug, PartialEq, Eq)]
+pub struct UnquotedError {
+    /// Slash separated path of the entry name or expression holding the string
+    pub path: String,
+
+    /// The string that couldn't be written
+    pub token: String,
+}
+
+impl fmt::Display for UnquotedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "'{}' at '{}' can't be written without quotes",
+            self.token, self.path
+        )
+    }
+}
+
+impl error::Error for UnquotedError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+/// Representation of an error resolving a path of entry names within a document
+///
+/// New variants may be added in future releases. Prefer the `is_*` predicates over exhaustive
+/// matching
+#[derive(Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PathError {
+    /// The path had no segments
+    Empty,
+
+    /// No entry exists at the path
+    NotFound(String),
+
+    /// The path names an expression rather than an entry
+    NotAnEntry(String),
+}
+
+impl PathError {
+    /// Returns `true` if the path had no segments
+    pub fn is_empty(&self) -> bool {
+        matches!(self, PathError::Empty)
+    }
+
+    /// Returns `true` if nothing exists at the path
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, PathError::NotFound(..))
+    }
+
+    /// Returns `true` if the path names an expression
+    pub fn is_not_an_entry(&self) -> bool {
+        matches!(self, PathError::NotAnEntry(..))
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "the path is empty"),
+            PathError::NotFound(path) => write!(f, "no entry found at '{}'", path),
+            PathError::NotAnEntry(path) => write!(f, "'{}' is a value, not an entry", path),
+        }
+    }
+}
+
+impl error::Error for PathError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acf_error_predicates() {
+        let err = AcfError::read("./missing.acf");
+        assert!(err.is_read());
+        assert!(!err.is_parse());
+        assert!(!err.is_unknown());
+        assert!(err.as_parse_error().is_none());
+
+        let err = AcfError::parse(ParseError::Unknown);
+        assert!(err.is_parse());
+        assert!(!err.is_read());
+        assert_eq!(err.as_parse_error(), Some(&ParseError::Unknown));
+
+        assert!(AcfError::default().is_unknown());
+
+        let err = AcfError::not_found("./missing.acf");
+        assert!(err.is_not_found());
+        assert!(!err.is_read());
+        assert_eq!(err.to_string(), "'./missing.acf' does not exist");
+    }
+
+    #[test]
+    fn parse_error_predicates() {
+        let err = ParseError::expected_closing_brace(Span::from(0..4));
+        assert!(err.is_expected_closing_brace());
+        assert!(!err.is_unknown());
+        assert!(ParseError::default().is_unknown());
+
+        let err = ParseError::duplicate_key(Span::new(2, 6));
+        assert!(err.is_duplicate_key());
+        assert_eq!(err.span(), Some(Span::new(2, 6)));
+
+        let err = ParseError::limit_exceeded(Limit::TotalKeys, 10, Span::new(4, 8));
+        assert!(err.is_limit_exceeded());
+        assert!(!err.is_expected_closing_brace());
+
+        match err {
+            ParseError::LimitExceeded { span: Span { start, end }, .. } => {
+                assert_eq!((start, end), (4, 8));
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use std::ops::Index;
+use std::slice;
+use std::vec;
+
+/// Ordered collection of an entry's expressions
+///
+/// Expressions are kept in the order they appear in the source, so documents serialize back the
+/// way they were read. The API mirrors the parts of `HashMap` that matter for lookups, with some
+/// differences:
+///
+/// - Keys may repeat, and lookups return the first matching expression. A `HashMap` built from
+///   the same document would hold the last one
+/// - Lookups scan the collection, so they take time linear in its length
+/// - Equality compares expressions in order, so the same pairs in a different order are unequal
+///
+/// With the `serde` feature, expressions serialize as a sequence of `[key, value]` pairs
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Expressions {
+    /// Key-value pairs in source order
+    items: Vec<(String, String)>,
+}
+
+impl Expressions {
+    /// Creates an empty collection
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of expressions, counting repeated keys individually
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if there are no expressions
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the value of the first expression with the given key
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.items
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns a mutable reference to the value of the first expression with the given key
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.items
+            .iter_mut()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns `true` if an expression with the given key exists
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.items.iter().any(|(name, _)| name == key)
+    }
+
+    /// Sets the value of the first expression with the given key, returning the previous value
+    ///
+    /// The expression keeps its position. If the key doesn't exist, the expression is appended
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        let key = key.into();
+        let value = value.into();
+
+        match self.get_mut(&key) {
+            Some(existing) => Some(std::mem::replace(existing, value)),
+            None => {
+                self.items.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Removes every expression with the given key, returning the first removed value
+    pub fn remove(&mut self, key: &str) -> Option<String> {
+        let index = self.items.iter().position(|(name, _)| name == key)?;
+        let (_, value) = self.items.remove(index);
+        self.items.retain(|(name, _)| name != key);
+        Some(value)
+    }
+
+    /// Retains only the expressions for which the predicate returns `true`
+    pub fn retain(&mut self, mut f: impl FnMut(&str, &str) -> bool) {
+        self.items.retain(|(key, value)| f(key, value))
+    }
+
+    /// Removes all expressions
+    pub fn clear(&mut self) {
+        self.items.clear()
+    }
+
+    /// Shrinks the collection and every key and value to fit their contents
+    pub fn shrink_to_fit(&mut self) {
+        self.items.shrink_to_fit();
+        self.items.iter_mut().for_each(|(key, value)| {
+            key.shrink_to_fit();
+            value.shrink_to_fit();
+        });
+    }
+
+    /// Estimates the heap memory, in bytes, retained by the collection
+    pub fn memory_usage(&self) -> usize {
+        let pairs = self.items.capacity() * std::mem::size_of::<(String, String)>();
+        let strings = self
+            .items
+            .iter()
+            .map(|(key, value)| key.capacity() + value.capacity())
+            .sum::<usize>();
+
+        pairs + strings
+    }
+
+    /// Iterates over key-value pairs in source order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.items.iter())
+    }
+
+    /// Iterates over keys in source order
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.items.iter().map(|(key, _)| key)
+    }
+
+    /// Iterates over values in source order
+    pub fn values(&self) -> impl Iterator<Item = &String> {
+        self.items.iter().map(|(_, value)| value)
+    }
+}
+
+impl Index<&str> for Expressions {
+    type Output = String;
+
+    /// Returns the value of the first expression with the given key
+    ///
+    /// # Panics
+    ///
+    /// Panics if no expression with the key exists
+    fn index(&self, key: &str) -> &String {
+        match self.get(key) {
+            Some(value) => value,
+            None => panic!("no expression found for key '{}'", key),
+        }
+    }
+}
+
+impl FromIterator<(String, String)> for Expressions {
+    fn from_iter<T: IntoIterator<Item = (String, String)>>(iter: T) -> Self {
+        Self {
+            items: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<(String, String)> for Expressions {
+    fn extend<T: IntoIterator<Item = (String, String)>>(&mut self, iter: T) {
+        self.items.extend(iter)
+    }
+}
+
+impl IntoIterator for Expressions {
+    type Item = (String, String);
+    type IntoIter = vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Expressions {
+    type Item = (&'a String, &'a String);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over an entry's expressions, in source order
+#[derive(Clone, Debug)]
+pub struct Iter<'a>(slice::Iter<'a, (String, String)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preserves_order() {
+        let mut expressions = Expressions::new();
+        expressions.insert("b", "1");
+        expressions.insert("a", "2");
+        expressions.insert("c", "3");
+        assert_eq!(expressions.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+
+        assert_eq!(expressions.insert("a", "4"), Some("2".into()));
+        assert_eq!(expressions.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(expressions["a"], "4");
+    }
+
+    #[test]
+    fn repeated_keys() {
+        let mut expressions = [("key", "1"), ("other", "2"), ("key", "3")]
+            .into_iter()
+            .map(|(key, value)| (key.to_owned(), value.to_owned()))
+            .collect::<Expressions>();
+        assert_eq!(expressions.len(), 3);
+        assert_eq!(expressions["key"], "1");
+
+        assert_eq!(expressions.remove("key"), Some("1".into()));
+        assert_eq!(expressions.len(), 1);
+        assert!(!expressions.contains_key("key"));
+    }
+}
@@ -0,0 +1,324 @@
+//! Build a shared or static library with the `ffi` feature enabled:
+//!
+//! ```text
+//! cargo rustc --release --features ffi --crate-type cdylib
+//! cargo rustc --release --features ffi --crate-type staticlib
+//! ```
+//!
+//! The matching header lives in `include/acf_parser.h` and is regenerated with
+//! `cbindgen --config cbindgen.toml --output include/acf_parser.h`
+//!
+//! # Conventions
+//!
+//! - All strings passed in must be NUL-terminated UTF-8. Strings written out are UTF-8 and
+//!   NUL-terminated
+//! - Functions that write into a caller-provided buffer return the length in bytes of the full
+//!   string, excluding the terminator. The string is only written if it fits, i.e. if the
+//!   returned length is less than `len`, so callers can retry with a larger buffer. Values
+//!   containing NUL bytes appear truncated to C
+//! - Negative return values are error codes (`ACF_ERR_*`). A description of the most recent
+//!   error on the calling thread is available through [`acf_last_error`]
+//! - Handles returned by [`acf_parse_file`] are owned by the caller and must be released with
+//!   [`acf_free`] exactly once
+//! - Panics never cross the boundary; they are reported as [`ACF_ERR_PANIC`]
+
+use crate::errors::AcfError;
+use crate::parser::{parse_acf, Acf, Entry};
+use std::cell::RefCell;
+use std::error::Error;
+use std::ffi::{c_char, CStr};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// The call succeeded
+pub const ACF_OK: i32 = 0;
+
+/// The requested path doesn't exist in the document
+pub const ACF_ERR_NOT_FOUND: i32 = -1;
+
+/// A required pointer was null or a string wasn't valid UTF-8
+pub const ACF_ERR_INVALID_ARGUMENT: i32 = -2;
+
+/// The file couldn't be read or parsed
+pub const ACF_ERR_PARSE: i32 = -3;
+
+/// A panic was caught inside the library
+pub const ACF_ERR_PANIC: i32 = -4;
+
+/// Opaque handle to a parsed document
+pub struct AcfHandle {
+    /// The parsed document
+    acf: Acf,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+/// Parses the ACF file at `path`
+///
+/// On success, stores a new handle in `*out_handle` and returns [`ACF_OK`]. On failure, stores
+/// null in `*out_handle` (when it isn't null itself) and returns a negative error code
+///
+/// # Safety
+///
+/// `path` must be null or point to a NUL-terminated string. `out_handle` must be null or valid
+/// for writes
+#[no_mangle]
+pub unsafe extern "C" fn acf_parse_file(
+    path: *const c_char,
+    out_handle: *mut *mut AcfHandle,
+) -> i32 {
+    guard(ACF_ERR_PANIC, || {
+        if out_handle.is_null() {
+            return invalid_argument("out_handle is null");
+        }
+        *out_handle = ptr::null_mut();
+
+        let path = match to_str(path) {
+            Some(val) => val,
+            None => return invalid_argument("path is null or not valid UTF-8"),
+        };
+
+        match parse_acf(path) {
+            Ok(acf) => {
+                *out_handle = Box::into_raw(Box::new(AcfHandle { acf }));
+                ACF_OK
+            }
+            Err(e) => {
+                set_last_error(describe(&e));
+                ACF_ERR_PARSE
+            }
+        }
+    })
+}
+
+/// Looks up a value by its slash separated path (e.g. `AppState/UserConfig/language`)
+///
+/// The first segment names a root entry, the last names an expression, and any in between name
+/// nested entries. The first match is used at every level. Returns the value's length, writing
+/// it to `buf` if it fits (see the module documentation), or a negative error code
+///
+/// # Safety
+///
+/// `handle` must be null or a live handle from [`acf_parse_file`]. `path` must be null or point
+/// to a NUL-terminated string. `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn acf_get(
+    handle: *const AcfHandle,
+    path: *const c_char,
+    buf: *mut c_char,
+    len: usize,
+) -> isize {
+    guard(ACF_ERR_PANIC as isize, || {
+        let handle = match handle.as_ref() {
+            Some(val) => val,
+            None => return invalid_argument("handle is null") as isize,
+        };
+
+        let path = match to_str(path) {
+            Some(val) => val,
+            None => return invalid_argument("path is null or not valid UTF-8") as isize,
+        };
+
+        match lookup(&handle.acf, path) {
+            Some(value) => write_str(value, buf, len),
+            None => {
+                set_last_error(format!("no value found at '{}'", path));
+                ACF_ERR_NOT_FOUND as isize
+            }
+        }
+    })
+}
+
+/// Retrieves the description of the most recent error on the calling thread
+///
+/// Returns the description's length, writing it to `buf` if it fits (see the module
+/// documentation). Returns zero if no error has occurred
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn acf_last_error(buf: *mut c_char, len: usize) -> isize {
+    guard(ACF_ERR_PANIC as isize, || {
+        LAST_ERROR.with(|last| write_str(&last.borrow(), buf, len))
+    })
+}
+
+/// Releases a handle returned by [`acf_parse_file`]. Passing null is a no-op
+///
+/// # Safety
+///
+/// `handle` must be null or a live handle from [`acf_parse_file`] that hasn't been freed
+#[no_mangle]
+pub unsafe extern "C" fn acf_free(handle: *mut AcfHandle) {
+    guard((), || {
+        if !handle.is_null() {
+            drop(Box::from_raw(handle));
+        }
+    })
+}
+
+/// Runs `f`, converting a panic into `on_panic` so it never unwinds into foreign code
+fn guard<T>(on_panic: T, f: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        set_last_error("a panic occurred inside acf-parser");
+        on_panic
+    })
+}
+
+/// Records the most recent error for the calling thread
+fn set_last_error(msg: impl Into<String>) {
+    LAST_ERROR.with(|last| *last.borrow_mut() = msg.into());
+}
+
+/// Records an invalid argument error, returning its code
+fn invalid_argument(msg: &str) -> i32 {
+    set_last_error(msg);
+    ACF_ERR_INVALID_ARGUMENT
+}
+
+/// Describes an error, including its source if it has one
+fn describe(err: &AcfError) -> String {
+    match err.source() {
+        Some(source) => format!("{}: {}", err, source),
+        None => err.to_string(),
+    }
+}
+
+/// Borrows a C string as UTF-8
+///
+/// # Safety
+///
+/// `val` must be null or point to a NUL-terminated string that outlives the result
+unsafe fn to_str<'a>(val: *const c_char) -> Option<&'a str> {
+    if val.is_null() {
+        None
+    } else {
+        CStr::from_ptr(val).to_str().ok()
+    }
+}
+
+/// Writes a NUL-terminated copy of `val` into `buf` if it fits, returning the length of `val`
+///
+/// # Safety
+///
+/// `buf` must be null or valid for writes of `len` bytes
+unsafe fn write_str(val: &str, buf: *mut c_char, len: usize) -> isize {
+    if !buf.is_null() && val.len() < len {
+        ptr::copy_nonoverlapping(val.as_ptr(), buf.cast::<u8>(), val.len());
+        *buf.add(val.len()) = 0;
+    }
+
+    isize::try_from(val.len()).unwrap_or(isize::MAX)
+}
+
+/// Resolves a slash separated path to an expression's value
+fn lookup<'a>(acf: &'a Acf, path: &str) -> Option<&'a str> {
+    let mut segments = path.split('/');
+    let root = segments.next()?;
+    let key = segments.next_back()?;
+
+    let mut entry = acf.entries.iter().find(|entry| entry.name == root)?;
+    for segment in segments {
+        entry = child(entry, segment)?;
+    }
+
+    entry.expressions.get(key).map(String::as_str)
+}
+
+/// Returns the first sub-entry with the given name
+fn child<'a>(entry: &'a Entry, name: &str) -> Option<&'a Entry> {
+    entry.entries.iter().find(|sub_entry| sub_entry.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    /// Reads the most recent error through the C API
+    fn last_error() -> String {
+        let mut buf = [0 as c_char; 256];
+        let len = unsafe { acf_last_error(buf.as_mut_ptr(), buf.len()) };
+        assert!(len >= 0 && (len as usize) < buf.len());
+        unsafe { CStr::from_ptr(buf.as_ptr()) }
+            .to_str()
+            .unwrap()
+            .to_owned()
+    }
+
+    /// Parses a file through the C API
+    fn open(path: &str) -> (i32, *mut AcfHandle) {
+        let path = CString::new(path).unwrap();
+        let mut handle = ptr::null_mut();
+        let status = unsafe { acf_parse_file(path.as_ptr(), &mut handle) };
+        (status, handle)
+    }
+
+    /// Looks up a value through the C API
+    fn get(handle: *const AcfHandle, path: &str, buf: &mut [c_char]) -> isize {
+        let path = CString::new(path).unwrap();
+        unsafe { acf_get(handle, path.as_ptr(), buf.as_mut_ptr(), buf.len()) }
+    }
+
+    #[test]
+    fn lookup_hits() {
+        let (status, handle) = open("./acfs/appmanifest_730.acf");
+        assert_eq!(status, ACF_OK);
+        assert!(!handle.is_null());
+
+        let mut buf = [0 as c_char; 64];
+        assert_eq!(get(handle, "AppState/name", &mut buf), 16);
+        let value = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(value.to_str().unwrap(), "Counter-Strike 2");
+
+        assert_eq!(get(handle, "AppState/InstalledDepots/731/size", &mut buf), 1);
+        let value = unsafe { CStr::from_ptr(buf.as_ptr()) };
+        assert_eq!(value.to_str().unwrap(), "8");
+
+        unsafe { acf_free(handle) };
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let (_, handle) = open("./acfs/appmanifest_730.acf");
+
+        let mut buf = [1 as c_char; 4];
+        assert_eq!(get(handle, "AppState/name", &mut buf), 16);
+        assert!(buf.iter().all(|&c| c == 1));
+
+        unsafe { acf_free(handle) };
+    }
+
+    #[test]
+    fn lookup_misses() {
+        let (_, handle) = open("./acfs/appmanifest_730.acf");
+
+        let mut buf = [0 as c_char; 64];
+        assert_eq!(get(handle, "AppState/missing", &mut buf), ACF_ERR_NOT_FOUND as isize);
+        assert!(last_error().contains("AppState/missing"));
+        assert_eq!(get(handle, "Missing/name", &mut buf), ACF_ERR_NOT_FOUND as isize);
+        assert_eq!(get(handle, "AppState", &mut buf), ACF_ERR_NOT_FOUND as isize);
+
+        unsafe { acf_free(handle) };
+    }
+
+    #[test]
+    fn errors() {
+        let (status, handle) = open("./acfs/missing.acf");
+        assert_eq!(status, ACF_ERR_PARSE);
+        assert!(handle.is_null());
+        assert!(last_error().contains("missing.acf"));
+
+        let status = unsafe { acf_parse_file(ptr::null(), &mut ptr::null_mut()) };
+        assert_eq!(status, ACF_ERR_INVALID_ARGUMENT);
+
+        let mut buf = [0 as c_char; 64];
+        let status = get(ptr::null(), "AppState/name", &mut buf);
+        assert_eq!(status, ACF_ERR_INVALID_ARGUMENT as isize);
+
+        unsafe { acf_free(ptr::null_mut()) };
+    }
+}
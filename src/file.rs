@@ -0,0 +1,196 @@
+use crate::errors::*;
+use crate::parser::{parse_acf_str, Acf};
+use crate::stamp::{fnv1a, load, Stamp};
+use std::fs;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// An editing session over a single ACF file
+///
+/// Keeps the file's path, its original text, and the parsed document. Changes made through
+/// [`acf_mut`](AcfFile::acf_mut) mark the session dirty, and [`save`](AcfFile::save) only writes
+/// when it is. Call [`is_modified_on_disk`](AcfFile::is_modified_on_disk) before saving to find
+/// out whether something else changed the file since it was loaded
+#[derive(Debug)]
+pub struct AcfFile {
+    /// Path of the file
+    path: String,
+
+    /// Text of the file when it was last loaded or saved
+    original: String,
+
+    /// The parsed document, including any unsaved changes
+    acf: Acf,
+
+    /// State of the file when it was last loaded or saved
+    stamp: Stamp,
+
+    /// Whether the document may differ from the file
+    dirty: bool,
+}
+
+impl AcfFile {
+    /// Loads and parses the file at `path`
+    pub fn open(path: &str) -> Result<Self> {
+        let (original, stamp) = load(path)?;
+        let acf = parse_acf_str(&original)?;
+
+        Ok(Self {
+            path: path.into(),
+            original,
+            acf,
+            stamp,
+            dirty: false,
+        })
+    }
+
+    /// Returns the path of the file
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the text of the file when it was last loaded or saved
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+
+    /// Returns the document
+    pub fn acf(&self) -> &Acf {
+        &self.acf
+    }
+
+    /// Returns the document for editing, marking the session dirty
+    pub fn acf_mut(&mut self) -> &mut Acf {
+        self.dirty = true;
+        &mut self.acf
+    }
+
+    /// Returns `true` if there may be unsaved changes
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns `true` if the file on disk no longer matches the text it was loaded from
+    ///
+    /// The modification time and length are checked first. If either changed, the contents are
+    /// compared, so merely touching the file doesn't count as a modification
+    pub fn is_modified_on_disk(&self) -> Result<bool> {
+        if Stamp::of(&self.path)?.matches_metadata(&self.stamp) {
+            return Ok(false);
+        }
+
+        let (contents, _) = load(&self.path)?;
+        Ok(contents != self.original)
+    }
+
+    /// Writes the document back to the file if the session is dirty
+    ///
+    /// Returns `true` if the file was written. A clean session doesn't touch the file at all.
+    /// The document is written in the layout Steam uses (see [`Acf::to_vdf_string`]) to a
+    /// temporary file next to the original, which then replaces it, so readers never observe a
+    /// partially written file
+    pub fn save(&mut self) -> Result<bool> {
+        if !self.dirty {
+            return Ok(false);
+        }
+
+        let contents = self.acf.to_vdf_string();
+        let temp = format!("{}.{}.tmp", self.path, std::process::id());
+
+        if fs::write(&temp, &contents).is_err() {
+            let _ = fs::remove_file(&temp);
+            return Err(AcfError::Write(self.path.clone()));
+        }
+        if fs::rename(&temp, &self.path).is_err() {
+            let _ = fs::remove_file(&temp);
+            return Err(AcfError::Write(self.path.clone()));
+        }
+
+        self.stamp = Stamp {
+            hash: fnv1a(contents.as_bytes()),
+            ..Stamp::of(&self.path)?
+        };
+        self.original = contents;
+        self.dirty = false;
+
+        Ok(true)
+    }
+
+    /// Discards unsaved changes, reloading the file from disk
+    ///
+    /// On error the session is left unchanged
+    pub fn revert(&mut self) -> Result<()> {
+        let (original, stamp) = load(&self.path)?;
+        let acf = parse_acf_str(&original)?;
+
+        self.original = original;
+        self.acf = acf;
+        self.stamp = stamp;
+        self.dirty = false;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_acf;
+    use crate::stamp::temp_copy;
+
+    #[test]
+    fn dirty_lifecycle() {
+        let path = temp_copy("file-dirty").to_string_lossy().into_owned();
+        let mut file = AcfFile::open(&path).unwrap();
+        assert!(!file.is_dirty());
+
+        file.acf_mut().entries[0].expressions.insert("buildid", "1");
+        assert!(file.is_dirty());
+
+        assert!(file.save().unwrap());
+        assert!(!file.is_dirty());
+        assert!(!file.is_modified_on_disk().unwrap());
+        assert!(!file.save().unwrap());
+
+        let saved = parse_acf(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(saved.unwrap().entries[0].expressions["buildid"], "1");
+    }
+
+    #[test]
+    fn external_change() {
+        let path = temp_copy("file-external").to_string_lossy().into_owned();
+        let mut file = AcfFile::open(&path).unwrap();
+        file.acf_mut().entries[0].name = "Edited".into();
+
+        let src = file.original().replace("20040392", "200403921");
+        fs::write(&path, &src).unwrap();
+        let modified = file.is_modified_on_disk();
+        let reverted = file.revert();
+        fs::remove_file(&path).unwrap();
+
+        assert!(modified.unwrap());
+        reverted.unwrap();
+        assert!(!file.is_dirty());
+        assert_eq!(file.original(), src);
+        assert_eq!(file.acf().entries[0].name, "AppState");
+        assert_eq!(file.acf().entries[0].expressions["buildid"], "200403921");
+    }
+
+    #[test]
+    fn clean_save_touches_nothing() {
+        let path = temp_copy("file-clean").to_string_lossy().into_owned();
+        let mut file = AcfFile::open(&path).unwrap();
+        let before = fs::metadata(&path).unwrap().modified().unwrap();
+
+        let saved = file.save();
+        let after = fs::metadata(&path).unwrap().modified().unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(!saved.unwrap());
+        assert_eq!(before, after);
+        assert_eq!(contents, file.original());
+    }
+}
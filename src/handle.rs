@@ -0,0 +1,161 @@
+use crate::errors::*;
+use crate::parser::{parse_acf_str_with_options, Acf, ParseOptions};
+use crate::stamp::{load, Stamp};
+use std::sync::{Arc, Mutex, RwLock};
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// A parsed ACF file that can be brought up to date with the file on disk
+///
+/// Readers take a snapshot with [`get`](AcfWatch::get), which is cheap and never blocks on a
+/// reparse for longer than it takes to swap a pointer. A snapshot is always either the previous
+/// document or the fully parsed new one, never a partial update. [`refresh`](AcfWatch::refresh)
+/// checks the file for changes and swaps in a new document if it changed
+///
+/// `AcfWatch` is `Send` and `Sync`, so one instance can be shared between reader threads and a
+/// thread that periodically refreshes it
+#[derive(Debug)]
+pub struct AcfWatch {
+    /// Path of the file
+    path: String,
+
+    /// Options applied to every load
+    options: ParseOptions,
+
+    /// The most recently parsed document
+    current: RwLock<Arc<Acf>>,
+
+    /// State of the file when `current` was loaded. Also serializes refreshes
+    stamp: Mutex<Stamp>,
+}
+
+impl AcfWatch {
+    /// Parses the file at `path`, returning a handle to it
+    pub fn open(path: &str) -> Result<Self> {
+        Self::with_options(path, ParseOptions::default())
+    }
+
+    /// Parses the file at `path` with the given options, which also apply to every refresh
+    pub fn with_options(path: &str, options: ParseOptions) -> Result<Self> {
+        let (contents, stamp) = load(path)?;
+        let acf = parse_acf_str_with_options(&contents, &options)?;
+
+        Ok(Self {
+            path: path.into(),
+            options,
+            current: RwLock::new(Arc::new(acf)),
+            stamp: Mutex::new(stamp),
+        })
+    }
+
+    /// Returns the path of the file
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns a snapshot of the current document
+    pub fn get(&self) -> Arc<Acf> {
+        Arc::clone(&self.current.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// Reloads the file if it changed, returning `true` if a new document was swapped in
+    ///
+    /// The modification time and length are checked first, so an unchanged file costs a single
+    /// `metadata` call. If either differs the file is read and its contents hashed, and it is
+    /// only reparsed if the contents actually changed. On error the current document is kept
+    ///
+    /// > NOTE: Filesystems with coarse timestamps may not register a rewrite that keeps the
+    /// > length and lands within the same timestamp tick as the previous one
+    pub fn refresh(&self) -> Result<bool> {
+        let mut stamp = self.stamp.lock().unwrap_or_else(|e| e.into_inner());
+
+        if Stamp::of(&self.path)?.matches_metadata(&stamp) {
+            return Ok(false);
+        }
+
+        let (contents, new_stamp) = load(&self.path)?;
+        if new_stamp.hash == stamp.hash {
+            *stamp = new_stamp;
+
+            #[cfg(feature = "tracing")]
+            tracing::debug!(path = %self.path, "contents unchanged");
+
+            return Ok(false);
+        }
+
+        let acf = parse_acf_str_with_options(&contents, &self.options)?;
+        *self.current.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(acf);
+        *stamp = new_stamp;
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(path = %self.path, "reloaded");
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stamp::temp_copy;
+    use std::fs;
+
+    #[test]
+    fn refresh_after_change() {
+        let path = temp_copy("handle-change");
+        let handle = AcfWatch::open(path.to_str().unwrap()).unwrap();
+        let before = handle.get();
+        assert_eq!(before.entries[0].expressions["buildid"], "20040392");
+
+        let src = fs::read_to_string(&path).unwrap().replace("20040392", "200403921");
+        fs::write(&path, src).unwrap();
+
+        let result = handle.refresh();
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.unwrap());
+        assert_eq!(handle.get().entries[0].expressions["buildid"], "200403921");
+        assert_eq!(before.entries[0].expressions["buildid"], "20040392");
+    }
+
+    #[test]
+    fn unchanged_fast_path() {
+        let path = temp_copy("handle-unchanged");
+        let handle = AcfWatch::open(path.to_str().unwrap()).unwrap();
+        let before = handle.get();
+
+        let first = handle.refresh();
+        fs::write(&path, fs::read_to_string(&path).unwrap()).unwrap();
+        let second = handle.refresh();
+        fs::remove_file(&path).unwrap();
+
+        assert!(!first.unwrap());
+        assert!(!second.unwrap());
+        assert!(Arc::ptr_eq(&before, &handle.get()));
+    }
+
+    #[test]
+    fn failed_refresh_keeps_snapshot() {
+        let path = temp_copy("handle-invalid");
+        let handle = AcfWatch::open(path.to_str().unwrap()).unwrap();
+        let before = handle.get();
+
+        fs::write(&path, "\"AppState\"\n{\n").unwrap();
+        let result = handle.refresh();
+        fs::remove_file(&path).unwrap();
+
+        assert!(result.is_err());
+        assert!(Arc::ptr_eq(&before, &handle.get()));
+
+        assert!(handle.refresh().unwrap_err().is_not_found());
+        assert!(Arc::ptr_eq(&before, &handle.get()));
+        assert!(AcfWatch::open(path.to_str().unwrap()).unwrap_err().is_not_found());
+    }
+
+    #[test]
+    fn watch_is_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<AcfWatch>();
+    }
+}
@@ -0,0 +1,293 @@
+use crate::errors::*;
+use crate::lexer::{Lexer, TokenKind};
+use crate::parser::{
+    check_depth, entry_parser, parse_acf_str, whitespace, Acf, Entry, DEFAULT_MAX_DEPTH,
+    DEFAULT_QUOTE_CHAR,
+};
+use chumsky::prelude::*;
+use std::ops::Range;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// A replacement of a byte range of source text
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct TextEdit {
+    /// Byte range of the original text being replaced
+    pub range: Range<usize>,
+
+    /// Text inserted in place of the range
+    pub text: String,
+}
+
+impl TextEdit {
+    /// Creates an edit replacing `range` with `text`
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Self {
+            range,
+            text: text.into(),
+        }
+    }
+
+    /// Applies the edit to the given source, returning the edited text
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds or doesn't lie on `char` boundaries
+    pub fn apply(&self, src: &str) -> String {
+        let mut edited = src.to_owned();
+        edited.replace_range(self.range.clone(), &self.text);
+
+        edited
+    }
+}
+
+/// How a document was brought up to date after an edit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Reparse {
+    /// Only the smallest entry enclosing the edit was reparsed
+    Incremental,
+
+    /// The whole document was reparsed
+    Full,
+}
+
+/// Brace structure of an entry in the source
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+#[derive(Debug)]
+struct Block {
+    /// Byte range of the whole entry, from its name to its closing brace
+    span: Range<usize>,
+
+    /// Byte range between the entry's braces
+    body: Range<usize>,
+
+    /// Sub-entries, in source order
+    children: Vec<Block>,
+}
+
+impl Acf {
+    /// Updates the document after an edit to the source it was parsed from
+    ///
+    /// `old_src` must be the text this document was parsed from. The smallest entry whose body
+    /// contains the edited range is located, and only that entry's new text is parsed and spliced
+    /// into the tree. Edits that touch an entry's name or braces are handled by the enclosing
+    /// entry, and edits outside any entry (or that leave the enclosing entry unparseable on its
+    /// own) fall back to a full reparse of the edited source
+    ///
+    /// Offsets are recomputed from `old_src` with an allocation free scan rather than stored in
+    /// the tree, so entries outside the edit never need their positions shifted
+    ///
+    /// On error the document is left unchanged
+    ///
+    /// # Panics
+    ///
+    /// Panics if the edit range is out of bounds or doesn't lie on `char` boundaries
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(range = ?edit.range))
+    )]
+    pub fn reparse_edit(&mut self, old_src: &str, edit: &TextEdit) -> Result<Reparse> {
+        let new_src = edit.apply(old_src);
+        check_depth(&new_src, DEFAULT_MAX_DEPTH, DEFAULT_QUOTE_CHAR).map_err(AcfError::Parse)?;
+
+        if let Some(blocks) = blocks(old_src) {
+            let path = enclosing(&blocks, &edit.range);
+            let entry = entry_at(&mut self.entries, &blocks, &path);
+            let target = block_at(&blocks, &path).zip(entry);
+
+            if let Some((block, entry)) = target {
+                let block_end = block.span.end - edit.range.len() + edit.text.len();
+                let result = entry_parser(DEFAULT_QUOTE_CHAR, false)
+                    .padded_by(whitespace())
+                    .then_ignore(end())
+                    .parse(&new_src[block.span.start..block_end])
+                    .into_result();
+
+                if let Ok(new_entry) = result {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(entry = %new_entry.name, "reparsed incrementally");
+
+                    *entry = new_entry;
+                    return Ok(Reparse::Incremental);
+                }
+            }
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!("falling back to a full reparse");
+
+        *self = parse_acf_str(&new_src)?;
+
+        Ok(Reparse::Full)
+    }
+}
+
+/// Recovers the brace structure of the source
+///
+/// Returns `None` if the source isn't well formed enough to trust the structure
+fn blocks(src: &str) -> Option<Vec<Block>> {
+    let mut roots = Vec::new();
+    let mut open: Vec<Block> = Vec::new();
+    let mut pending = None;
+
+    for token in Lexer::new(src) {
+        match token.kind {
+            TokenKind::Str => {
+                pending = match pending {
+                    Some(_) => None,
+                    None => Some(token.start),
+                }
+            }
+            TokenKind::Open => open.push(Block {
+                span: pending.take()?..token.end,
+                body: token.end..token.end,
+                children: Vec::new(),
+            }),
+            TokenKind::Close => {
+                if pending.is_some() {
+                    return None;
+                }
+
+                let mut block = open.pop()?;
+                block.span.end = token.end;
+                block.body.end = token.start;
+
+                match open.last_mut() {
+                    Some(parent) => parent.children.push(block),
+                    None => roots.push(block),
+                }
+            }
+            TokenKind::Invalid => return None,
+        }
+    }
+
+    (open.is_empty() && pending.is_none()).then_some(roots)
+}
+
+/// Returns the index path to the deepest block whose body contains the range
+fn enclosing(blocks: &[Block], range: &Range<usize>) -> Vec<usize> {
+    let mut path = Vec::new();
+    let mut level = blocks;
+
+    while let Some((index, block)) = level
+        .iter()
+        .enumerate()
+        .find(|(_, block)| block.body.start <= range.start && range.end <= block.body.end)
+    {
+        path.push(index);
+        level = &block.children;
+    }
+
+    path
+}
+
+/// Returns the block at the given index path
+fn block_at<'a>(blocks: &'a [Block], path: &[usize]) -> Option<&'a Block> {
+    let (&index, rest) = path.split_first()?;
+    let block = blocks.get(index)?;
+
+    if rest.is_empty() {
+        Some(block)
+    } else {
+        block_at(&block.children, rest)
+    }
+}
+
+/// Returns the entry at the given index path, provided the tree's shape matches the blocks
+fn entry_at<'a>(
+    entries: &'a mut [Entry],
+    blocks: &[Block],
+    path: &[usize],
+) -> Option<&'a mut Entry> {
+    let (&index, rest) = path.split_first()?;
+    if entries.len() != blocks.len() {
+        return None;
+    }
+
+    let entry = entries.get_mut(index)?;
+    if rest.is_empty() {
+        Some(entry)
+    } else {
+        entry_at(&mut entry.entries, &blocks[index].children, rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn fixture() -> (String, Acf) {
+        let src = fs::read_to_string("./acfs/appmanifest_730.acf").unwrap();
+        let acf = parse_acf_str(&src).unwrap();
+        (src, acf)
+    }
+
+    /// Applies the edit incrementally and checks the result against a from-scratch parse
+    fn check(src: &str, mut acf: Acf, edit: TextEdit, expected: Reparse) {
+        assert_eq!(acf.reparse_edit(src, &edit).unwrap(), expected);
+        assert_eq!(acf, parse_acf_str(&edit.apply(src)).unwrap());
+    }
+
+    #[test]
+    fn value_edit() {
+        let (src, acf) = fixture();
+        let start = src.find("20040392").unwrap();
+        let edit = TextEdit::new(start..start + 8, "20040393");
+        check(&src, acf, edit, Reparse::Incremental);
+    }
+
+    #[test]
+    fn nested_value_edit() {
+        let (src, acf) = fixture();
+        let start = src.find("48989642342").unwrap();
+        let edit = TextEdit::new(start..start + 11, "1");
+        check(&src, acf, edit, Reparse::Incremental);
+    }
+
+    #[test]
+    fn structural_edit() {
+        let (src, acf) = fixture();
+        let anchor = "\t\t\"optionaldlc\"\t\t\"2279721\"\n";
+        let start = src.find(anchor).unwrap() + anchor.len();
+        let edit = TextEdit::new(start..start, "\t\t\"Extra\"\n\t\t{\n\t\t\t\"key\"\t\t\"value\"\n\t\t}\n");
+        check(&src, acf, edit, Reparse::Incremental);
+    }
+
+    #[test]
+    fn removes_entry() {
+        let (src, acf) = fixture();
+        let start = src.find("\t\t\"732\"").unwrap();
+        let end = src.find("\t\t\"731\"").unwrap();
+        check(&src, acf, TextEdit::new(start..end, ""), Reparse::Incremental);
+    }
+
+    #[test]
+    fn crosses_block_boundaries() {
+        let (src, acf) = fixture();
+        let anchor = "\t\t\"optionaldlc\"\t\t\"2279721\"\n";
+        let start = src.find(anchor).unwrap() + anchor.len();
+        let anchor = "\t\"MountedConfig\"\n\t{\n";
+        let end = src.find(anchor).unwrap() + anchor.len();
+        check(&src, acf, TextEdit::new(start..end, ""), Reparse::Incremental);
+    }
+
+    #[test]
+    fn outside_entries() {
+        let (src, acf) = fixture();
+        let start = src.find("\"AppState\"").unwrap();
+        let edit = TextEdit::new(start..start + 10, "\"Renamed\"");
+        check(&src, acf, edit, Reparse::Full);
+    }
+
+    #[test]
+    fn invalid_edit_leaves_tree() {
+        let (src, mut acf) = fixture();
+        let start = src.find("\"SharedDepots\"").unwrap();
+        let edit = TextEdit::new(start..start + 14, "");
+        assert!(acf.reparse_edit(&src, &edit).is_err());
+        assert_eq!(acf, parse_acf_str(&src).unwrap());
+    }
+}
@@ -0,0 +1,183 @@
+use crate::parser::{Acf, Entry};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Shared storage for strings repeated across a batch of documents
+///
+/// Scans over many files see the same entry names and keys over and over, and often the same
+/// values too (e.g. the `installdir` of every manifest in a library). Interning a parsed
+/// document copies it into an [`InternedAcf`] whose strings point into this table, so each
+/// distinct string is stored once however many documents hold it. Keep the interned documents
+/// and drop the parsed ones to benefit
+///
+/// Entry names and keys are always interned. Values are only interned when enabled with
+/// [`Interner::with_values`], as they repeat far less often and every distinct value stays in the
+/// table for as long as the interner lives
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    /// Every distinct string interned so far
+    strings: HashSet<Arc<str>>,
+
+    /// Whether values are interned as well as names and keys
+    values: bool,
+}
+
+impl Interner {
+    /// Creates an interner sharing entry names and keys, but not values
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an interner sharing values as well as entry names and keys
+    pub fn with_values() -> Self {
+        Self {
+            values: true,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `true` if values are interned
+    pub fn interns_values(&self) -> bool {
+        self.values
+    }
+
+    /// Returns the number of distinct strings in the table
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns `true` if nothing has been interned yet
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Returns the shared copy of `val`, adding it to the table if it isn't there yet
+    pub fn intern(&mut self, val: &str) -> Arc<str> {
+        if let Some(existing) = self.strings.get(val) {
+            return Arc::clone(existing);
+        }
+
+        let shared = Arc::<str>::from(val);
+        self.strings.insert(Arc::clone(&shared));
+        shared
+    }
+
+    /// Copies a document into interned storage
+    ///
+    /// Comments captured while parsing aren't carried over
+    pub fn intern_acf(&mut self, acf: &Acf) -> InternedAcf {
+        InternedAcf {
+            entries: acf
+                .entries
+                .iter()
+                .map(|entry| self.intern_entry(entry))
+                .collect(),
+        }
+    }
+
+    /// Copies an entry and its sub-entries into interned storage
+    pub fn intern_entry(&mut self, entry: &Entry) -> InternedEntry {
+        let expressions = entry
+            .expressions
+            .iter()
+            .map(|(key, value)| {
+                let value = if self.values {
+                    self.intern(value)
+                } else {
+                    Arc::from(value.as_str())
+                };
+                (self.intern(key), value)
+            })
+            .collect();
+
+        InternedEntry {
+            name: self.intern(&entry.name),
+            expressions,
+            entries: entry
+                .entries
+                .iter()
+                .map(|sub| self.intern_entry(sub))
+                .collect(),
+        }
+    }
+}
+
+/// A document whose strings are shared through an [`Interner`]
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct InternedAcf {
+    /// Root entries, in source order
+    pub entries: Vec<InternedEntry>,
+}
+
+/// An entry whose strings are shared through an [`Interner`]
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct InternedEntry {
+    /// Name of the entry
+    pub name: Arc<str>,
+
+    /// Key-value pairs, in source order
+    pub expressions: Vec<(Arc<str>, Arc<str>)>,
+
+    /// Sub-entries, in source order
+    pub entries: Vec<InternedEntry>,
+}
+
+impl InternedEntry {
+    /// Returns the value of the first expression with the given key
+    pub fn get(&self, key: &str) -> Option<&Arc<str>> {
+        self.expressions
+            .iter()
+            .find(|(name, _)| &**name == key)
+            .map(|(_, value)| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_acf;
+
+    #[test]
+    fn shares_keys() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let mut interner = Interner::new();
+        let first = interner.intern_acf(&acf);
+        let second = interner.intern_acf(&acf);
+        assert_eq!(first, second);
+
+        let (first, second) = (&first.entries[0], &second.entries[0]);
+        assert!(Arc::ptr_eq(&first.name, &second.name));
+        assert!(Arc::ptr_eq(
+            &first.expressions[0].0,
+            &second.expressions[0].0
+        ));
+        assert!(!Arc::ptr_eq(
+            first.get("installdir").unwrap(),
+            second.get("installdir").unwrap()
+        ));
+        assert_eq!(
+            &**first.get("installdir").unwrap(),
+            "Counter-Strike Global Offensive"
+        );
+    }
+
+    #[test]
+    fn shares_values() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let mut interner = Interner::with_values();
+        let first = interner.intern_acf(&acf);
+        let second = interner.intern_acf(&acf);
+        let len = interner.len();
+
+        let (first, second) = (&first.entries[0], &second.entries[0]);
+        assert!(Arc::ptr_eq(
+            first.get("installdir").unwrap(),
+            second.get("installdir").unwrap()
+        ));
+        assert!(Arc::ptr_eq(
+            first.get("appid").unwrap(),
+            &interner.intern("730")
+        ));
+        assert_eq!(interner.len(), len);
+    }
+}
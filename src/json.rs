@@ -0,0 +1,228 @@
+use crate::parser::{Acf, Entry};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+impl Acf {
+    /// Serializes the document as compact JSON
+    ///
+    /// The document becomes an object mapping root entry names to objects. Within an entry,
+    /// expressions become string members and sub-entries become nested objects, in source order.
+    /// JSON names should be unique, so where a name repeats (or an expression and a sub-entry
+    /// share one) only the first occurrence is written, matching [`Expressions::get`]
+    ///
+    /// [`Expressions::get`]: crate::expressions::Expressions::get
+    pub fn to_json_string(&self) -> String {
+        let mut out = String::new();
+        write_object(&mut out, &[], &self.entries, None);
+
+        out
+    }
+
+    /// Serializes the document as indented JSON for reading
+    ///
+    /// Uses the same mapping as [`Acf::to_json_string`], with each member on its own line,
+    /// indented by two spaces per level. Empty objects are written as `{}`
+    pub fn to_pretty_json(&self) -> String {
+        let mut out = String::new();
+        write_object(&mut out, &[], &self.entries, Some(0));
+
+        out
+    }
+
+    /// Serializes every expression as a line of JSON (JSON Lines), for feeding log or data
+    /// pipelines
+    ///
+    /// Each line is an object of the form `{"path":...,"key":...,"value":...}`, where the path is
+    /// the slash separated path of the expression's entry, as given by [`Acf::iter_leaves`].
+    /// Lines follow the same order, and every line ends with a newline. Unlike
+    /// [`Acf::to_json_string`], repeated names are all written
+    pub fn to_jsonl(&self) -> String {
+        let mut out = String::new();
+        for (path, key, value) in self.iter_leaves() {
+            out.push_str("{\"path\":");
+            write_string(&mut out, &path);
+            out.push_str(",\"key\":");
+            write_string(&mut out, key);
+            out.push_str(",\"value\":");
+            write_string(&mut out, value);
+            out.push_str("}\n");
+        }
+
+        out
+    }
+}
+
+impl Entry {
+    /// Serializes the entry's contents as a compact JSON object
+    ///
+    /// Uses the same mapping as [`Acf::to_json_string`]. The entry's own name isn't included
+    pub fn to_json_string(&self) -> String {
+        let mut out = String::new();
+        write_entry(&mut out, self, None);
+
+        out
+    }
+}
+
+/// Writes an entry's contents as an object
+///
+/// `indent` is the nesting level of the object when writing indented output, or `None` for
+/// compact output
+fn write_entry(out: &mut String, entry: &Entry, indent: Option<usize>) {
+    let expressions = entry.expressions.iter().collect::<Vec<_>>();
+    write_object(out, &expressions, &entry.entries, indent);
+}
+
+/// Writes an object of string members followed by nested objects, skipping repeated names
+fn write_object(
+    out: &mut String,
+    expressions: &[(&String, &String)],
+    entries: &[Entry],
+    indent: Option<usize>,
+) {
+    let mut seen = HashSet::new();
+    let nested = indent.map(|level| level + 1);
+    out.push('{');
+
+    for (key, value) in expressions {
+        if seen.insert(key.as_str()) {
+            write_separator(out, seen.len(), nested);
+            write_string(out, key);
+            write_colon(out, indent);
+            write_string(out, value);
+        }
+    }
+
+    for entry in entries {
+        if seen.insert(entry.name.as_str()) {
+            write_separator(out, seen.len(), nested);
+            write_string(out, &entry.name);
+            write_colon(out, indent);
+            write_entry(out, entry, nested);
+        }
+    }
+
+    if let Some(level) = indent.filter(|_| !seen.is_empty()) {
+        write_newline(out, level);
+    }
+    out.push('}');
+}
+
+/// Writes a comma before every member but the first, then starts the member's line when
+/// indenting
+fn write_separator(out: &mut String, count: usize, indent: Option<usize>) {
+    if count > 1 {
+        out.push(',');
+    }
+    if let Some(level) = indent {
+        write_newline(out, level);
+    }
+}
+
+/// Writes the colon between a member's name and value
+fn write_colon(out: &mut String, indent: Option<usize>) {
+    match indent {
+        Some(_) => out.push_str(": "),
+        None => out.push(':'),
+    }
+}
+
+/// Starts a new line indented to the given nesting level
+fn write_newline(out: &mut String, level: usize) {
+    out.push('\n');
+    out.push_str(&"  ".repeat(level));
+}
+
+/// Writes a JSON string literal, escaping as required
+fn write_string(out: &mut String, val: &str) {
+    out.push('"');
+
+    for c in val.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0C}' => out.push_str("\\f"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::*;
+
+    #[test]
+    fn to_json_string() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&acf.to_json_string()).unwrap();
+
+        let app_state = &json["AppState"];
+        assert_eq!(app_state["appid"], "730");
+        assert_eq!(app_state["LauncherPath"], "C:\\\\Program Files (x86)\\\\Steam\\\\steam.exe");
+        assert_eq!(app_state["InstalledDepots"]["731"]["size"], "8");
+        assert_eq!(app_state["UserConfig"]["DisabledDLC"], "");
+
+        let depots = app_state["InstalledDepots"].as_object().unwrap();
+        assert_eq!(depots.len(), 7);
+    }
+
+    #[test]
+    fn escapes_and_repeats() {
+        let src = "\"Root\" { \"text\" \"tab\there\nnew\x01\" \"text\" \"second\" \"text\" { } }";
+        let acf = parse_acf_str(src).unwrap();
+        assert_eq!(
+            acf.to_json_string(),
+            "{\"Root\":{\"text\":\"tab\\there\\nnew\\u0001\"}}"
+        );
+        assert_eq!(acf.entries[0].to_json_string(), "{\"text\":\"tab\\there\\nnew\\u0001\"}");
+    }
+
+    #[test]
+    fn to_pretty_json() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let pretty = acf.to_pretty_json();
+
+        let json: serde_json::Value = serde_json::from_str(&pretty).unwrap();
+        let compact: serde_json::Value = serde_json::from_str(&acf.to_json_string()).unwrap();
+        assert_eq!(json, compact);
+
+        assert!(pretty.starts_with("{\n  \"AppState\": {\n    \"appid\": \"730\",\n"));
+        assert!(pretty.contains("\n      \"731\": {\n        \"manifest\": "));
+        assert!(pretty.ends_with("\n  }\n}"));
+
+        let src = "\"Root\" { \"Empty\" { } }";
+        let acf = parse_acf_str(src).unwrap();
+        assert_eq!(acf.to_pretty_json(), "{\n  \"Root\": {\n    \"Empty\": {}\n  }\n}");
+    }
+
+    #[test]
+    fn to_jsonl() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let jsonl = acf.to_jsonl();
+        let lines = jsonl.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), acf.leaf_paths().len());
+        assert_eq!(lines[0], "{\"path\":\"AppState\",\"key\":\"appid\",\"value\":\"730\"}");
+        assert!(jsonl.ends_with("}\n"));
+
+        let records = lines
+            .iter()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        let depot = records
+            .iter()
+            .find(|record| record["path"] == "AppState/InstalledDepots/731")
+            .unwrap();
+        assert_eq!(depot["key"], "manifest");
+        assert_eq!(depot["value"], "8593492943720630015");
+    }
+}
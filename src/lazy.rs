@@ -0,0 +1,299 @@
+use crate::errors::*;
+use crate::expressions::Expressions;
+use crate::parser::{
+    check_depth, entry_parser, expr_parser, literal_parser, str_parser, syntax_error, whitespace,
+    Acf, Entry, Extra, ParseOptions, DEFAULT_MAX_DEPTH, DEFAULT_QUOTE_CHAR,
+};
+use crate::span::Span;
+use chumsky::prelude::*;
+use std::cell::OnceCell;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// Lazily parsed ACF content
+///
+/// Root entries have their expressions parsed eagerly, while nested blocks are only recorded as
+/// spans of the source text. A sub-entry is parsed the first time it is accessed, so reading a
+/// couple of top-level values never pays for large sections like `InstalledDepots`
+#[derive(Debug)]
+pub struct LazyAcf<'src> {
+    /// A list of root entries
+    pub entries: Vec<LazyEntry<'src>>,
+}
+
+/// Representation of a root ACF entry with lazily parsed sub-entries
+#[derive(Debug)]
+pub struct LazyEntry<'src> {
+    /// Name of the entry
+    pub name: String,
+
+    /// A list of expressions
+    pub expressions: Expressions,
+
+    /// A list of unparsed sub-entries
+    children: Vec<LazyChild<'src>>,
+}
+
+/// An unparsed sub-entry, materialized on first access
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+#[derive(Debug)]
+struct LazyChild<'src> {
+    /// Name of the sub-entry
+    name: String,
+
+    /// Source text of the sub-entry, including its name
+    source: &'src str,
+
+    /// Byte offset of the sub-entry within the document
+    offset: usize,
+
+    /// The parsed sub-entry, once it has parsed successfully
+    entry: OnceCell<Entry>,
+}
+
+impl LazyChild<'_> {
+    /// Returns the parsed sub-entry, parsing it if this is the first access
+    ///
+    /// Failures aren't cached, so every access to a broken sub-entry reports its error again
+    fn get(&self) -> Result<&Entry> {
+        if let Some(entry) = self.entry.get() {
+            return Ok(entry);
+        }
+
+        let entry = entry_parser(DEFAULT_QUOTE_CHAR, false)
+            .padded_by(whitespace())
+            .then_ignore(end())
+            .parse(self.source)
+            .into_result()
+            .map_err(|errs| {
+                let err = syntax_error(self.source, errs, &ParseOptions::default());
+                AcfError::Parse(shifted(err, self.offset))
+            })?;
+
+        Ok(self.entry.get_or_init(|| entry))
+    }
+}
+
+impl LazyAcf<'_> {
+    /// Fully parses every sub-entry, producing the same result as the eager parser
+    pub fn to_acf(&self) -> Result<Acf> {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| entry.to_entry())
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Acf { entries })
+    }
+}
+
+impl LazyEntry<'_> {
+    /// Returns the names of the immediate sub-entries without parsing them
+    pub fn child_names(&self) -> Vec<&str> {
+        self.children.iter().map(|child| child.name.as_str()).collect()
+    }
+
+    /// Returns the first sub-entry with the given name, parsing it if required
+    ///
+    /// Errors within the sub-entry are reported here rather than when the document was parsed
+    pub fn child(&self, name: &str) -> Result<Option<&Entry>> {
+        match self.children.iter().find(|child| child.name == name) {
+            Some(child) => child.get().map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Parses every sub-entry, returning them in source order
+    pub fn children(&self) -> Result<Vec<&Entry>> {
+        self.children.iter().map(|child| child.get()).collect()
+    }
+
+    /// Fully parses the entry, producing the same result as the eager parser
+    pub fn to_entry(&self) -> Result<Entry> {
+        Ok(Entry {
+            name: self.name.clone(),
+            expressions: self.expressions.clone(),
+            entries: self.children()?.into_iter().cloned().collect(),
+            comments: Vec::new(),
+        })
+    }
+}
+
+/// Lazy ACF parser
+///
+/// Parses the expressions of each root entry, recording nested blocks for later. Only the brace
+/// structure of nested blocks is checked up front; any other error within a block surfaces when
+/// it is first accessed
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "debug", skip_all, fields(bytes = src.len()))
+)]
+pub fn parse_acf_lazy(src: &str) -> Result<LazyAcf<'_>> {
+    check_depth(src, DEFAULT_MAX_DEPTH, DEFAULT_QUOTE_CHAR).map_err(AcfError::Parse)?;
+
+    let entries = match lazy_acf_parser().parse(src).into_result() {
+        Ok(val) => val,
+        Err(errs) => {
+            let err = syntax_error(src, errs, &ParseOptions::default());
+            return Err(AcfError::Parse(err));
+        }
+    };
+
+    let entries = entries
+        .into_iter()
+        .map(|(name, expressions, children)| LazyEntry {
+            name,
+            expressions,
+            children: children
+                .into_iter()
+                .map(|(name, span)| LazyChild {
+                    name,
+                    source: &src[span.start..span.end],
+                    offset: span.start,
+                    entry: OnceCell::new(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    Ok(LazyAcf { entries })
+}
+
+/// Moves the location of an error found within a sub-entry's source by `offset` bytes, so it
+/// points into the whole document
+fn shifted(err: ParseError, offset: usize) -> ParseError {
+    let shift = |span: Span| Span::new(span.start + offset, span.end + offset);
+
+    match err {
+        ParseError::ExpectedClosingBrace(span) => ParseError::ExpectedClosingBrace(shift(span)),
+        ParseError::LimitExceeded { which, limit, span } => {
+            ParseError::limit_exceeded(which, limit, shift(span))
+        }
+        ParseError::Syntax { span, message } => ParseError::syntax(shift(span), message),
+        ParseError::DuplicateKey(span) => ParseError::DuplicateKey(shift(span)),
+        ParseError::Unknown => ParseError::Unknown,
+    }
+}
+
+/// Raw output of the lazy parser: entry name, expressions, and sub-entry names with their spans
+type RawLazyEntry = (String, Expressions, Vec<(String, SimpleSpan)>);
+
+/// Lazy root entry parser
+///
+/// Mirrors the entry parser, but skips over sub-entries instead of parsing them
+fn lazy_acf_parser<'src>() -> impl Parser<'src, &'src str, Vec<RawLazyEntry>, Extra<'src>> {
+    let child = str_parser(DEFAULT_QUOTE_CHAR)
+        .then_ignore(block_skipper())
+        .map_with(|name, e| (name, e.span()));
+
+    str_parser(DEFAULT_QUOTE_CHAR)
+        .padded_by(whitespace())
+        .then_ignore(just("{").padded_by(whitespace()))
+        .then(
+            expr_parser(DEFAULT_QUOTE_CHAR)
+                .padded_by(whitespace())
+                .repeated()
+                .collect::<Vec<_>>(),
+        )
+        .then(child.padded_by(whitespace()).repeated().collect::<Vec<_>>())
+        .then_ignore(just("}").padded_by(whitespace()))
+        .map(|((name, expressions), children)| {
+            let expressions = expressions
+                .into_iter()
+                .map(|expr| (expr.name, expr.value))
+                .collect();
+
+            (name, expressions, children)
+        })
+        .padded_by(whitespace())
+        .repeated()
+        .collect::<Vec<_>>()
+        .then_ignore(end())
+}
+
+/// Block skipper
+///
+/// Matches a brace delimited block, including any nested blocks, without interpreting its
+/// contents. String literals and line comments are respected so braces within them don't affect
+/// nesting
+fn block_skipper<'src>() -> impl Parser<'src, &'src str, (), Extra<'src>> {
+    recursive(|block| {
+        let string = literal_parser(DEFAULT_QUOTE_CHAR).ignored();
+        let comment = just("//").then(none_of('\n').repeated()).ignored();
+
+        just('{')
+            .then(choice((string, comment, block, none_of("\"{}").ignored())).repeated())
+            .then(just('}'))
+            .ignored()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_acf, parse_acf_str};
+    use std::fs;
+
+    #[test]
+    fn shallow_access() {
+        let src = fs::read_to_string("./acfs/appmanifest_730.acf").unwrap();
+        let lazy = parse_acf_lazy(&src).unwrap();
+        let root_entry = &lazy.entries[0];
+        assert_eq!(root_entry.name, "AppState");
+        assert_eq!(root_entry.expressions["appid"], "730");
+        assert_eq!(root_entry.expressions["name"], "Counter-Strike 2");
+        assert_eq!(
+            root_entry.child_names(),
+            ["InstalledDepots", "SharedDepots", "UserConfig", "MountedConfig"]
+        );
+    }
+
+    #[test]
+    fn deep_access_matches_eager() {
+        let eager = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let src = fs::read_to_string("./acfs/appmanifest_730.acf").unwrap();
+        let lazy = parse_acf_lazy(&src).unwrap();
+
+        let depots = lazy.entries[0].child("InstalledDepots").unwrap().unwrap();
+        assert_eq!(depots, &eager.entries[0].entries[0]);
+        assert!(lazy.entries[0].child("Missing").unwrap().is_none());
+        assert_eq!(lazy.to_acf().unwrap(), eager);
+    }
+
+    #[test]
+    fn error_surfaces_on_access() {
+        let src = r#""AppState" { "appid" "730" "Good" { "a" "b" } "Bad" { "x" } }"#;
+        let lazy = parse_acf_lazy(src).unwrap();
+        let root_entry = &lazy.entries[0];
+        assert_eq!(root_entry.expressions["appid"], "730");
+        assert!(root_entry.child("Good").unwrap().is_some());
+        assert!(root_entry.child("Bad").is_err());
+        assert!(lazy.to_acf().is_err());
+
+        let err = root_entry.child("Bad").unwrap_err();
+        let eager = parse_acf_str(src).unwrap_err();
+        assert!(err.as_parse_error().unwrap().is_syntax());
+        assert_eq!(err.as_parse_error().unwrap().span(), eager.as_parse_error().unwrap().span());
+    }
+
+    #[test]
+    fn comments_in_skipped_blocks() {
+        let src = "\"AppState\" { \"Child\" { // \"quoted\" }\n \"a\" \"b\" } }";
+        let lazy = parse_acf_lazy(src).unwrap();
+        let child = lazy.entries[0].child("Child").unwrap().unwrap();
+        assert_eq!(child.expressions["a"], "b");
+    }
+
+    #[test]
+    fn unbalanced_braces_fail_up_front() {
+        let src = r#""AppState" { "appid" "730" "Bad" { "a" "b" }"#;
+        assert!(parse_acf_lazy(src).is_err());
+
+        let src = r#""AppState" { "appid" "730" junk "Good" { "a" "b" } }"#;
+        let err = parse_acf_lazy(src).unwrap_err();
+        let span = err.as_parse_error().unwrap().span().unwrap();
+        assert_eq!(span.start, src.find("junk").unwrap());
+    }
+}
@@ -0,0 +1,265 @@
+use crate::parser::DEFAULT_QUOTE_CHAR;
+
+/// Characters VDF treats as whitespace between tokens
+///
+/// Spaces, tabs, carriage returns, line feeds, vertical tabs, and form feeds. Other Unicode
+/// whitespace (such as non-breaking spaces) is not a separator
+pub(crate) const WHITESPACE: &str = " \t\r\n\x0B\x0C";
+
+/// Checks whether a character can be part of a bare word, i.e. an unquoted string such as the
+/// name in `AppState {`
+pub(crate) fn is_bare_char(c: char, quote: char) -> bool {
+    !WHITESPACE.contains(c) && !matches!(c, '{' | '}') && c != quote
+}
+
+/// Returns the length in bytes of a string literal's contents, i.e. the offset of its closing
+/// quote within `contents`, or `None` if the literal is unterminated
+///
+/// A backslash always takes the following character with it, so escaped quotes are skipped
+pub(crate) fn literal_len(contents: &str, quote: char) -> Option<usize> {
+    let mut chars = contents.char_indices();
+    while let Some((offset, c)) = chars.next() {
+        if c == quote {
+            return Some(offset);
+        }
+        if c == '\\' {
+            chars.next();
+        }
+    }
+
+    None
+}
+
+/// Kind of a lexical token
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum TokenKind {
+    /// A quoted string literal
+    Str,
+
+    /// An opening brace
+    Open,
+
+    /// A closing brace
+    Close,
+
+    /// Anything that can't start a valid token, including unterminated strings
+    Invalid,
+}
+
+/// A lexical token, referencing a byte range of the source
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Token {
+    /// Kind of the token
+    pub(crate) kind: TokenKind,
+
+    /// Byte offset of the first character of the token
+    pub(crate) start: usize,
+
+    /// Byte offset one past the last character of the token
+    pub(crate) end: usize,
+
+    /// Whether a [`TokenKind::Str`] token is a bare word rather than a quoted literal
+    pub(crate) bare: bool,
+}
+
+impl Token {
+    /// Length in bytes of a string literal's contents, excluding the given quotes
+    pub(crate) fn content_len(&self, quote: char) -> usize {
+        if self.bare {
+            return self.end - self.start;
+        }
+        (self.end - self.start).saturating_sub(2 * quote.len_utf8())
+    }
+
+    /// Returns a string literal's contents, excluding the given quotes
+    pub(crate) fn contents<'src>(&self, src: &'src str, quote: char) -> &'src str {
+        if self.bare {
+            return &src[self.start..self.end];
+        }
+        &src[self.start + quote.len_utf8()..self.end - quote.len_utf8()]
+    }
+}
+
+/// Allocation free tokenizer
+///
+/// Splits ACF source into string literals and braces, mirroring the grammar's notion of a token.
+/// Whitespace and `//` line comments between tokens are skipped
+///
+/// This is used by scanning passes that must not build a tree; the chumsky grammar remains the
+/// authority on what is valid
+pub(crate) struct Lexer<'src> {
+    /// Source being tokenized
+    src: &'src str,
+
+    /// Byte offset of the next unread character
+    pos: usize,
+
+    /// Character delimiting string literals
+    quote: char,
+
+    /// Whether bare words are read as strings
+    bare_words: bool,
+}
+
+impl<'src> Lexer<'src> {
+    /// Creates a lexer over the given source
+    pub(crate) fn new(src: &'src str) -> Self {
+        Self::with_quote(src, DEFAULT_QUOTE_CHAR)
+    }
+
+    /// Creates a lexer over the given source, delimiting strings with `quote`
+    pub(crate) fn with_quote(src: &'src str, quote: char) -> Self {
+        Self {
+            src,
+            pos: 0,
+            quote,
+            bare_words: false,
+        }
+    }
+
+    /// Reads runs of characters that can't start any other token as strings, marked as
+    /// [`Token::bare`], rather than as invalid characters
+    pub(crate) fn with_bare_words(mut self, enabled: bool) -> Self {
+        self.bare_words = enabled;
+        self
+    }
+
+    /// Advances past any whitespace and line comments
+    fn skip_whitespace(&mut self) {
+        loop {
+            let rest = &self.src[self.pos..];
+            let trimmed = rest.trim_start_matches(|c: char| WHITESPACE.contains(c));
+            self.pos += rest.len() - trimmed.len();
+
+            if !trimmed.starts_with("//") {
+                return;
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+}
+
+impl Iterator for Lexer<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        self.skip_whitespace();
+        let trimmed = &self.src[self.pos..];
+
+        let start = self.pos;
+        let first = trimmed.chars().next()?;
+        let mut bare = false;
+        let kind = match first {
+            '{' => {
+                self.pos += 1;
+                TokenKind::Open
+            }
+            '}' => {
+                self.pos += 1;
+                TokenKind::Close
+            }
+            quote if quote == self.quote => match literal_len(&trimmed[quote.len_utf8()..], quote) {
+                Some(len) => {
+                    self.pos += len + 2 * quote.len_utf8();
+                    TokenKind::Str
+                }
+                None => {
+                    self.pos = self.src.len();
+                    TokenKind::Invalid
+                }
+            },
+            _ if self.bare_words && is_bare_char(first, self.quote) => {
+                let quote = self.quote;
+                self.pos += trimmed
+                    .find(|c| !is_bare_char(c, quote))
+                    .unwrap_or(trimmed.len());
+                bare = true;
+                TokenKind::Str
+            }
+            _ => {
+                self.pos += first.len_utf8();
+                TokenKind::Invalid
+            }
+        };
+
+        Some(Token {
+            kind,
+            start,
+            end: self.pos,
+            bare,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokens() {
+        let kinds = Lexer::new("\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n}")
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Str,
+                TokenKind::Open,
+                TokenKind::Str,
+                TokenKind::Str,
+                TokenKind::Close
+            ]
+        );
+    }
+
+    #[test]
+    fn unterminated_string() {
+        let tokens = Lexer::new("\"appid").collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].kind, TokenKind::Invalid);
+    }
+
+    #[test]
+    fn escaped_quotes() {
+        let src = r#""say \"hi\"" "C:\\" "\"#;
+        let tokens = Lexer::new(src).collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].contents(src, '"'), r#"say \"hi\""#);
+        assert_eq!(tokens[1].contents(src, '"'), r"C:\\");
+        assert_eq!(tokens[2].kind, TokenKind::Invalid);
+    }
+
+    #[test]
+    fn custom_quote() {
+        let tokens = Lexer::with_quote("'appid' 'say \"hi\"'", '\'').collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens.iter().all(|token| token.kind == TokenKind::Str));
+        assert_eq!(tokens[1].content_len('\''), 8);
+    }
+
+    #[test]
+    fn bare_words() {
+        let src = "AppState // comment\n{ \"appid\" \"730\" }";
+        let tokens = Lexer::new(src).with_bare_words(true).collect::<Vec<_>>();
+        assert_eq!(tokens.len(), 5);
+        assert!(tokens[0].bare);
+        assert_eq!(tokens[0].kind, TokenKind::Str);
+        assert_eq!(tokens[0].contents(src, '"'), "AppState");
+        assert_eq!(tokens[2].contents(src, '"'), "appid");
+        assert!(!tokens[2].bare);
+
+        let tokens = Lexer::new(src).collect::<Vec<_>>();
+        assert_eq!(tokens[0].kind, TokenKind::Invalid);
+    }
+
+    #[test]
+    fn whitespace_and_comments() {
+        let kinds = Lexer::new("// \"quoted\" {\n\"a\"\x0C{\x0B}\u{A0}")
+            .map(|token| token.kind)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            [TokenKind::Str, TokenKind::Open, TokenKind::Close, TokenKind::Invalid]
+        );
+    }
+}
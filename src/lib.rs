@@ -1,14 +1,92 @@
 #![doc = include_str!("../README.md")]
 
+/// Binary VDF decoding
+pub mod binary;
+/// Python bindings
+#[cfg(feature = "python")]
+mod bindings;
+/// Binary caching of parsed trees
+#[cfg(feature = "cache")]
+pub mod cache;
+/// Syntax checking without building a tree
+pub mod check;
+/// Deserializing user types from entries
+#[cfg(feature = "serde")]
+pub mod de;
+/// Human readable debug output
+mod debug;
+/// Structural differences between documents
+pub mod diff;
+/// Tree editing operations
+pub mod edit;
 /// Project specific errors
 pub mod errors;
+/// Ordered expression storage
+pub mod expressions;
+/// C API for embedding the parser in non-Rust tools
+#[cfg(feature = "ffi")]
+pub mod ffi;
+/// Editing sessions over ACF files
+pub mod file;
+/// Auto-reloading handles to ACF files
+pub mod handle;
+/// Incremental reparsing after text edits
+pub mod incremental;
+/// Shared storage for strings repeated across documents
+pub mod intern;
+/// Dependency free JSON output
+#[cfg(feature = "json")]
+mod json;
+/// Lazy parsing of nested entries
+pub mod lazy;
+/// Allocation free tokenizer used by scanning passes
+mod lexer;
+/// Byte offset to line and column conversion
+pub mod line_index;
+/// Typed access to Steam's `loginusers.vdf`
+pub mod login_users;
+/// Memory footprint management
+mod memory;
 /// Parsing functionality
 pub mod parser;
+/// Tree queries
+mod query;
+/// Serializing user types into entries
+#[cfg(feature = "serde")]
+pub mod ser;
+/// Serialization back into the ACF format
+pub mod serializer;
+/// Crate owned source spans
+pub mod span;
+/// Change detection for files on disk
+mod stamp;
+/// Proptest strategies for generating documents
+#[cfg(feature = "test-strategies")]
+pub mod strategies;
+/// Tracing instrumentation helpers
+#[cfg(feature = "tracing")]
+mod trace;
+/// Typed value accessors
+mod values;
+/// JS bindings for running the parser in the browser
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
+/// Streaming ACF output
+pub mod writer;
 
 /// A collection of common requirements
 pub mod prelude {
     #[doc(hidden)]
-    pub use crate::parser::{parse_acf, Acf};
+    pub use crate::expressions::Expressions;
+
+    #[doc(hidden)]
+    pub use crate::lazy::{parse_acf_lazy, LazyAcf};
+
+    #[doc(hidden)]
+    pub use crate::parser::{
+        parse_acf, parse_acf_str, parse_acf_str_with_options, parse_acf_with_meta,
+        parse_acf_with_options, Acf, ParseOptions, DEFAULT_MAX_DEPTH, DEFAULT_QUOTE_CHAR,
+    };
 
     #[doc(hidden)]
     pub use crate::errors::AcfError;
@@ -0,0 +1,64 @@
+/// Precomputed line starts of a source text
+///
+/// Converting a byte offset (such as the start of an error's span) into a line and column
+/// normally means rescanning the text from the beginning. `LineIndex` records where every line
+/// starts once, so each conversion only has to look at the line containing the offset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineIndex<'src> {
+    /// Source text being indexed
+    src: &'src str,
+
+    /// Byte offset of the start of every line
+    line_starts: Vec<usize>,
+}
+
+impl<'src> LineIndex<'src> {
+    /// Indexes the given source text
+    pub fn new(src: &'src str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(src.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Self { src, line_starts }
+    }
+
+    /// Returns the number of lines in the source
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Converts a byte offset into a 1-based `(line, column)` pair
+    ///
+    /// Columns count characters rather than bytes, so multi-byte UTF-8 content earlier on the
+    /// line doesn't skew the result. Offsets past the end of the source are clamped to the end,
+    /// and offsets within a multi-byte character resolve to that character
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let mut offset = offset.min(self.src.len());
+        while !self.src.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.src[line_start..offset].chars().count() + 1;
+
+        (line, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_col() {
+        let src = "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n}\n";
+        let index = LineIndex::new(src);
+        assert_eq!(index.line_count(), 5);
+        assert_eq!(index.line_col(0), (1, 1));
+        assert_eq!(index.line_col(11), (2, 1));
+        assert_eq!(index.line_col(src.find("appid").unwrap()), (3, 3));
+        assert_eq!(index.line_col(src.len()), (5, 1));
+        assert_eq!(index.line_col(usize::MAX), (5, 1));
+    }
+}
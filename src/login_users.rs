@@ -0,0 +1,114 @@
+use crate::errors::*;
+use crate::parser::{parse_acf, Acf, Entry};
+use std::time::SystemTime;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// An account that has signed in to Steam, as listed in `loginusers.vdf`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoginUser {
+    /// 64-bit Steam ID the account is listed under
+    pub steam_id: u64,
+
+    /// Name used to sign in (`AccountName`)
+    pub account_name: String,
+
+    /// Name shown to other users (`PersonaName`)
+    pub persona_name: String,
+
+    /// Whether this is the account that signed in most recently (`MostRecent`)
+    pub most_recent: bool,
+
+    /// Time of the account's last sign in (`Timestamp`)
+    pub timestamp: SystemTime,
+}
+
+impl LoginUser {
+    /// Reads an account from its entry within `users`, named by its Steam ID
+    pub fn from_entry(entry: &Entry) -> Result<Self> {
+        let steam_id = entry
+            .name
+            .parse::<u64>()
+            .map_err(|e| invalid("steam ID", &entry.name, e))?;
+        let most_recent = match entry.expressions.get("MostRecent").map(String::as_str) {
+            Some("1") => true,
+            Some("0") => false,
+            Some(value) => return Err(invalid("MostRecent", value, "expected 0 or 1")),
+            None => return Err(AcfError::Value(ValueError::missing("MostRecent"))),
+        };
+
+        Ok(Self {
+            steam_id,
+            account_name: entry.parse_value("AccountName")?,
+            persona_name: entry.parse_value("PersonaName")?,
+            most_recent,
+            timestamp: entry.get_unix_time("Timestamp")?,
+        })
+    }
+}
+
+/// Reads every account from a `loginusers.vdf` file (found in Steam's `config` directory)
+///
+/// Accounts are returned in the order they're listed. Fails if the file has no root `users`
+/// entry, or if any account is missing one of the fields of [`LoginUser`]
+pub fn parse_login_users(path: &str) -> Result<Vec<LoginUser>> {
+    login_users(&parse_acf(path)?)
+}
+
+/// Reads every account from the contents of a `loginusers.vdf` file, as for
+/// [`parse_login_users`]
+pub fn login_users(acf: &Acf) -> Result<Vec<LoginUser>> {
+    let users = acf
+        .entries
+        .iter()
+        .find(|entry| entry.name == "users")
+        .ok_or_else(|| AcfError::Value(ValueError::missing("users")))?;
+
+    users.entries.iter().map(LoginUser::from_entry).collect()
+}
+
+/// Creates an invalid value error
+fn invalid(key: &str, value: &str, reason: impl std::fmt::Display) -> AcfError {
+    AcfError::Value(ValueError::invalid(key, value, reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn login_users() {
+        let users = parse_login_users("./acfs/loginusers.vdf").unwrap();
+        assert_eq!(users.len(), 2);
+
+        assert_eq!(users[0].steam_id, 76561197960287930);
+        assert_eq!(users[0].account_name, "gaben");
+        assert_eq!(users[0].persona_name, "Rabscuttle");
+        assert!(users[0].most_recent);
+        assert_eq!(
+            users[0].timestamp,
+            UNIX_EPOCH + Duration::from_secs(1727712000)
+        );
+
+        assert_eq!(users[1].persona_name, "Player Two");
+        assert!(!users[1].most_recent);
+    }
+
+    #[test]
+    fn login_users_errors() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let err = super::login_users(&acf).unwrap_err();
+        assert!(err.as_value_error().unwrap().is_missing());
+
+        let src = "\"users\" { \"76561197960287930\" { \"AccountName\" \"gaben\" } }";
+        let err = super::login_users(&parse_acf_str(src).unwrap()).unwrap_err();
+        assert!(err.as_value_error().unwrap().is_missing());
+
+        let src = "\"users\" { \"gaben\" { } }";
+        let err = super::login_users(&parse_acf_str(src).unwrap()).unwrap_err();
+        assert!(err.as_value_error().unwrap().is_invalid());
+    }
+}
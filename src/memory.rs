@@ -0,0 +1,113 @@
+use crate::parser::{Acf, Entry};
+use std::mem;
+
+impl Acf {
+    /// Releases excess capacity held anywhere in the tree
+    ///
+    /// Parsing grows strings and vectors as it goes, leaving slack capacity behind. Call this
+    /// before keeping a document resident for a long time
+    pub fn shrink_to_fit(&mut self) {
+        self.entries.shrink_to_fit();
+        self.entries.iter_mut().for_each(Entry::shrink_to_fit);
+    }
+
+    /// Estimates the heap memory, in bytes, retained by the document
+    ///
+    /// Counts the capacity of every string and collection in the tree. Allocator overhead is not
+    /// included, so treat the result as a lower bound suitable for monitoring trends
+    pub fn memory_usage(&self) -> usize {
+        self.entries.capacity() * mem::size_of::<Entry>()
+            + self.entries.iter().map(Entry::heap_usage).sum::<usize>()
+    }
+}
+
+impl Entry {
+    /// Releases excess capacity held by the entry and its sub-entries
+    pub fn shrink_to_fit(&mut self) {
+        self.name.shrink_to_fit();
+        self.expressions.shrink_to_fit();
+        self.entries.shrink_to_fit();
+        self.entries.iter_mut().for_each(Entry::shrink_to_fit);
+        self.comments.shrink_to_fit();
+        self.comments.iter_mut().for_each(|(key, comment)| {
+            key.shrink_to_fit();
+            comment.shrink_to_fit();
+        });
+    }
+
+    /// Estimates the heap memory, in bytes, retained by the entry, including its own size
+    pub fn memory_usage(&self) -> usize {
+        mem::size_of::<Entry>() + self.heap_usage()
+    }
+
+    /// Heap memory owned by the entry, excluding the entry itself
+    fn heap_usage(&self) -> usize {
+        self.name.capacity()
+            + self.expressions.memory_usage()
+            + self.entries.capacity() * mem::size_of::<Entry>()
+            + self.entries.iter().map(Entry::heap_usage).sum::<usize>()
+            + self.comments.capacity() * mem::size_of::<(String, String)>()
+            + self
+                .comments
+                .iter()
+                .map(|(key, comment)| key.capacity() + comment.capacity())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::*;
+
+    /// Builds a wide tree with deliberately over-allocated strings and vectors
+    fn generated() -> Acf {
+        let mut root = Entry {
+            name: String::with_capacity(64),
+            ..Default::default()
+        };
+        root.name.push_str("Root");
+
+        for i in 0..1_000 {
+            let mut child = Entry {
+                name: String::with_capacity(64),
+                ..Default::default()
+            };
+            child.name.push_str(&i.to_string());
+
+            let mut value = String::with_capacity(128);
+            value.push_str("value");
+            child.expressions.insert("key", value);
+
+            let mut comment = String::with_capacity(128);
+            comment.push_str("comment");
+            child.comments.push(("key".to_owned(), comment));
+            root.entries.push(child);
+        }
+
+        Acf {
+            entries: vec![root],
+        }
+    }
+
+    #[test]
+    fn shrink_fixture() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let before = acf.memory_usage();
+        acf.shrink_to_fit();
+        let after = acf.memory_usage();
+
+        assert!(after > 0);
+        assert!(after <= before);
+        assert_eq!(acf, parse_acf("./acfs/appmanifest_730.acf").unwrap());
+    }
+
+    #[test]
+    fn shrink_generated() {
+        let mut acf = generated();
+        let before = acf.memory_usage();
+        acf.shrink_to_fit();
+        let after = acf.memory_usage();
+
+        assert!(after < before / 2, "before: {}, after: {}", before, after);
+    }
+}
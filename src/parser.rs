@@ -1,76 +1,854 @@
 use crate::errors::*;
+use crate::expressions::Expressions;
+use crate::lexer::{is_bare_char, Lexer, Token, TokenKind, WHITESPACE};
+use crate::line_index::LineIndex;
+use crate::span::Span;
+#[cfg(feature = "tracing")]
+use crate::trace;
 use chumsky::prelude::*;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::fs;
-use std::collections::HashMap;
+use std::io::{BufRead, Read};
+use std::time::SystemTime;
 
 // Error handling
 type Result<T> = std::result::Result<T, AcfError>;
 
+/// Parser configuration shared by every combinator
+pub(crate) type Extra<'src> = extra::Err<Rich<'src, char>>;
+
 /// Representation of an ACF's file content
 /// 
-/// Results are returned in the form of a hash map. Valve ACF files are expected
+/// Results are returned in the form of ordered maps. Valve ACF files are expected
 /// to have a root level entry (`AppState`) containing the app's ID, path, name,
 /// and filesystem specific information
 #[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Acf {
     /// A list of entries. Valve ACF files should have at least `AppState`
     pub entries: Vec<Entry>,
 }
 
 /// Representation of an individual ACF entry
-#[derive(Clone, Debug, PartialEq, Eq, Default)]
+///
+/// Entries compare equal when their names, expressions, and sub-entries match. Comments captured
+/// with [`ParseOptions::capture_comments`] are ignored
+#[derive(Clone, Debug, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Entry {
     /// Name of the entry
     pub name: String,
 
-    // A list of expressions
-    pub expressions: HashMap<String, String>,
+    // A list of expressions, in source order
+    pub expressions: Expressions,
 
     // A list of sub-entries
     pub entries: Vec<Entry>,
+
+    /// Comments attached to the entry's expressions as `(key, comment)`, in source order. Only
+    /// filled in when parsing with [`ParseOptions::capture_comments`], and never serialized
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Vec::is_empty"))]
+    pub(crate) comments: Vec<(String, String)>,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.expressions == other.expressions
+            && self.entries == other.entries
+    }
+}
+
+impl Entry {
+    /// Returns the names of the immediate sub-entries, in source order
+    pub fn child_names(&self) -> Vec<&str> {
+        self.entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    /// Returns the keys of the entry's expressions exactly as they appeared in the source,
+    /// including any repeats
+    pub fn key_order(&self) -> Vec<&str> {
+        self.expressions.keys().map(String::as_str).collect()
+    }
+
+    /// Returns the position of the first immediate sub-entry with the given name
+    pub fn child_index(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+    }
+
+    /// Returns the number of expressions in the entry and all of its sub-entries, recursively
+    pub fn total_expression_count(&self) -> usize {
+        let nested = self.entries.iter().map(Entry::total_expression_count);
+        self.expressions.len() + nested.sum::<usize>()
+    }
+
+    /// Returns the value of the first expression in source order, whatever its key
+    pub fn first_value(&self) -> Option<&str> {
+        self.expressions.values().next().map(String::as_str)
+    }
+
+    /// Returns the value of the first expression with the given key, including any line breaks
+    /// within it
+    ///
+    /// Quoted values may span several lines. They are returned exactly as written between the
+    /// quotes (apart from `\t` escapes), so embedded `\n` or `\r\n` line breaks and the
+    /// indentation of continuation lines are kept rather than trimmed
+    pub fn get_multiline(&self, key: &str) -> Option<&str> {
+        self.expressions.get(key).map(String::as_str)
+    }
+
+    /// Returns the value of the first expression with the given key, with escape sequences
+    /// expanded
+    ///
+    /// `\t` is decoded while parsing, but `\n`, `\r`, `\\`, and `\"` are stored as written. Here
+    /// they're expanded into a line feed, a carriage return, a single backslash, and a quote.
+    /// Other backslash sequences are kept as written. The value is only copied if there's
+    /// something to expand
+    pub fn get_cow(&self, key: &str) -> Option<Cow<'_, str>> {
+        self.expressions.get(key).map(|value| expand_escapes(value))
+    }
+
+    /// Returns the comment attached to the first expression with the given key
+    ///
+    /// Comments are only captured when parsing with [`ParseOptions::capture_comments`], which
+    /// describes how they're attached
+    pub fn comment_for(&self, key: &str) -> Option<&str> {
+        self.comments
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, comment)| comment.as_str())
+    }
+
+    /// Attaches a comment to the first expression with the given key, returning the comment it
+    /// replaces
+    ///
+    /// The comment is kept whether or not the key exists, so it can be set before the expression
+    /// is inserted
+    pub fn set_comment(&mut self, key: &str, comment: impl Into<String>) -> Option<String> {
+        let comment = comment.into();
+
+        match self.comments.iter_mut().find(|(name, _)| name == key) {
+            Some((_, existing)) => Some(std::mem::replace(existing, comment)),
+            None => {
+                self.comments.push((key.to_owned(), comment));
+                None
+            }
+        }
+    }
+
+    /// Copies the expressions into a standard, unordered `HashMap`
+    ///
+    /// Where a key is repeated the first value is kept, matching [`Expressions::get`]
+    pub fn to_map(&self) -> HashMap<String, String> {
+        self.expressions
+            .iter()
+            .rev()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+}
+
+/// Default maximum nesting depth of entries
+///
+/// Real Valve files rarely nest more than a dozen levels deep. The grammar is recursive, so this
+/// bound keeps pathological input from exhausting the stack
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
+/// Default character delimiting string literals
+pub const DEFAULT_QUOTE_CHAR: char = '"';
+
+/// Options controlling how ACF content is parsed
+///
+/// All limits default to unlimited. Set them when parsing untrusted input to bound the work and
+/// memory a single document can demand. Limits are checked by a streaming pass over the input
+/// that stops at the first breach, before any of the tree is allocated
+///
+/// Parsing takes time linear in the size of the input: each pre-parse check is a single pass over
+/// the tokens, and the grammar never backtracks further than the current token. Recursion is
+/// bounded by the depth limit, which is always enforced. Setting `max_input_bytes` therefore
+/// bounds the total work, and is checked before anything else
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Maximum size of the input in bytes
+    pub max_input_bytes: Option<usize>,
+
+    /// Maximum number of expressions across the whole document
+    pub max_total_keys: Option<usize>,
+
+    /// Maximum number of entries across the whole document, including root entries
+    pub max_total_entries: Option<usize>,
+
+    /// Maximum length, in bytes, of any single string (entry names, keys, and values)
+    pub max_value_len: Option<usize>,
+
+    /// Maximum nesting depth of entries, where root entries have a depth of one. Defaults to
+    /// [`DEFAULT_MAX_DEPTH`] when unset. Raising it far beyond the default risks overflowing the
+    /// stack of threads with small stacks
+    pub max_depth: Option<usize>,
+
+    /// Maximum number of expressions directly within any single entry, not counting those of its
+    /// sub-entries
+    pub max_expressions_per_entry: Option<usize>,
+
+    /// Character delimiting string literals. Defaults to [`DEFAULT_QUOTE_CHAR`]; set it to `'\''`
+    /// for single-quoted dialects. Only one quote character is recognized per document, so mixed
+    /// quoting isn't supported. Whitespace, braces, and `/` can't be used as quotes
+    pub quote_char: char,
+
+    /// Rejects backslash sequences other than `\t`, `\n`, `\r`, `\\`, and an escaped quote
+    /// within strings. By default unknown sequences such as `\q` are kept as written
+    pub strict_escapes: bool,
+
+    /// Records `//` comments next to expressions, readable with [`Entry::comment_for`]. A comment
+    /// on the same line as an expression, or on the lines directly above its key, is attached to
+    /// that expression, with several lines joined by `\n`. Comments anywhere else are dropped.
+    /// Off by default. Comments don't take part in comparing entries
+    pub capture_comments: bool,
+
+    /// Accepts entry names written without quotes, as in `AppState { "appid" "730" }`, like
+    /// Valve's own parser. A bare name runs up to the next whitespace, quote, or brace, and is
+    /// kept exactly as written. Keys and values must still be quoted
+    pub unquoted_names: bool,
+
+    /// How keys repeated within an entry are handled. By default every occurrence is kept, as
+    /// written. Same-named entries are unaffected; see [`Acf::dedup_entries`] for those
+    pub on_duplicate: DuplicatePolicy,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_input_bytes: None,
+            max_total_keys: None,
+            max_total_entries: None,
+            max_value_len: None,
+            max_depth: None,
+            max_expressions_per_entry: None,
+            quote_char: DEFAULT_QUOTE_CHAR,
+            strict_escapes: false,
+            capture_comments: false,
+            unquoted_names: false,
+            on_duplicate: DuplicatePolicy::KeepAll,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Returns `true` if any resource limit is set
+    fn has_limits(&self) -> bool {
+        self.max_total_keys.is_some()
+            || self.max_total_entries.is_some()
+            || self.max_value_len.is_some()
+            || self.max_expressions_per_entry.is_some()
+    }
+
+    /// Returns the effective maximum nesting depth
+    fn depth_limit(&self) -> usize {
+        self.max_depth.unwrap_or(DEFAULT_MAX_DEPTH)
+    }
+
+    /// Creates a lexer reading the syntax these options accept
+    fn lexer<'src>(&self, src: &'src str) -> Lexer<'src> {
+        Lexer::with_quote(src, self.quote_char).with_bare_words(self.unquoted_names)
+    }
+}
+
+/// How a key repeated within a single entry is handled while parsing
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum DuplicatePolicy {
+    /// Keep only the first expression with each key
+    KeepFirst,
+
+    /// Keep only the last expression with each key, at its own position
+    KeepLast,
+
+    /// Keep every expression, as written
+    #[default]
+    KeepAll,
+
+    /// Reject the document with [`ParseError::DuplicateKey`], spanning the first repeated
+    /// expression. This is checked before the grammar runs, so it's reported ahead of any later
+    /// syntax error
+    Error,
 }
 
 /// Representation of an individual ACF expression (of form "*."\s+"*.")
 /// 
 /// > NOTE: This is an internal representation that is not shown to the user
 #[derive(Clone, Debug, PartialEq, Eq)]
-struct Expr {
+pub(crate) struct Expr {
     /// Name of the expression
-    name: String,
+    pub(crate) name: String,
 
     /// Value of the expression
-    value: String,
+    pub(crate) value: String,
 }
 
 /// ACF file parser
 ///
 /// An ACF file is just a list of ACF entries. The current implementation returns a vector of
 /// entries, but expects a single root entry. It will not parse files that have additional entries given
+///
+/// Parsing never panics, whatever the input. Malformed, truncated, binary, or absurdly nested
+/// content is reported as an `Err`; nesting deeper than [`DEFAULT_MAX_DEPTH`] is rejected before
+/// the recursive grammar runs. The same guarantee holds for every parsing entry point
+///
+/// A file that doesn't exist is reported as [`AcfError::NotFound`], and any other failure to read
+/// it (e.g. a permission error) as [`AcfError::Read`]. On targets without a filesystem, such as
+/// `wasm32-unknown-unknown`, reading always fails with [`AcfError::Read`]. Use [`parse_acf_str`]
+/// there
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
 pub fn parse_acf(path: &str) -> Result<Acf> {
-    let contents = match fs::read_to_string(path) {
+    let contents = read_file(path)?;
+
+    parse_contents(&contents, &ParseOptions::default())
+}
+
+/// ACF file parser with options
+///
+/// Behaves like [`parse_acf`], applying the given [`ParseOptions`]
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+pub fn parse_acf_with_options(path: &str, options: &ParseOptions) -> Result<Acf> {
+    let contents = read_file(path)?;
+
+    parse_contents(&contents, options)
+}
+
+/// ACF reader parser with progress reporting
+///
+/// Reads the whole of `reader` before parsing, calling `on_progress` with the total number of
+/// bytes read so far after every chunk. The reader's buffer size determines how often progress is
+/// reported. Content that isn't valid UTF-8 is reported as a read error
+pub fn parse_acf_reader_progress<R: BufRead>(
+    mut reader: R,
+    mut on_progress: impl FnMut(usize),
+) -> Result<Acf> {
+    let read_error = || AcfError::Read("input stream".into());
+    let mut contents = Vec::new();
+
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok(val) => val,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(_) => return Err(read_error()),
+        };
+        if chunk.is_empty() {
+            break;
+        }
+
+        let len = chunk.len();
+        contents.extend_from_slice(chunk);
+        reader.consume(len);
+        on_progress(contents.len());
+    }
+
+    let contents = String::from_utf8(contents).map_err(|_| read_error())?;
+    parse_contents(&contents, &ParseOptions::default())
+}
+
+/// ACF string parser
+///
+/// Behaves like [`parse_acf`], but parses content already held in memory
+pub fn parse_acf_str(src: &str) -> Result<Acf> {
+    parse_contents(src, &ParseOptions::default())
+}
+
+/// ACF string parser with options
+///
+/// Behaves like [`parse_acf_str`], applying the given [`ParseOptions`]
+pub fn parse_acf_str_with_options(src: &str, options: &ParseOptions) -> Result<Acf> {
+    parse_contents(src, options)
+}
+
+/// Returns whether the ACF content parses successfully, discarding the tree
+///
+/// Applies the same rules as [`parse_acf_str`]. To find out why content is rejected, use
+/// [`check_acf`] or the error returned by the parser instead
+///
+/// [`check_acf`]: crate::check::check_acf
+pub fn is_valid_acf(src: &str) -> bool {
+    parse_acf_str(src).is_ok()
+}
+
+/// Multi-document ACF string parser
+///
+/// Splits a dump of several documents separated by form feeds (`\f`) and parses each one
+/// independently, returning a result per document in order. Form feeds within string literals
+/// don't split documents, and chunks holding only whitespace (e.g. after a trailing form feed)
+/// are skipped. Error spans are relative to the start of their document
+pub fn parse_acf_multi(src: &str) -> Vec<Result<Acf>> {
+    let mut separators = Vec::new();
+    let mut gap_start = 0;
+    let mut find_separators = |start: usize, end: usize| {
+        let gap = src[start..end].match_indices('\x0C');
+        separators.extend(gap.map(|(offset, _)| start + offset));
+    };
+
+    for token in Lexer::new(src) {
+        find_separators(gap_start, token.start);
+        gap_start = token.end;
+    }
+    find_separators(gap_start, src.len());
+
+    let mut start = 0;
+    separators
+        .into_iter()
+        .chain([src.len()])
+        .filter_map(|end| {
+            let chunk = &src[start..end];
+            start = end + 1;
+            let is_blank = chunk.trim_matches(|c: char| WHITESPACE.contains(c)).is_empty();
+            (!is_blank).then(|| parse_acf_str(chunk))
+        })
+        .collect()
+}
+
+/// ACF string parser returning a line index
+///
+/// Behaves like [`parse_acf_str`], but also returns a [`LineIndex`] over the source so the spans
+/// of any error can be converted into lines and columns without rescanning the input
+pub fn parse_acf_str_indexed(src: &str) -> (Result<Acf>, LineIndex<'_>) {
+    (parse_acf_str(src), LineIndex::new(src))
+}
+
+/// ACF file parser returning the file's modification time
+///
+/// Behaves like [`parse_acf`], but also returns the modification time of the file. The metadata
+/// is queried from the same open handle the contents are read from, avoiding a separate
+/// `metadata` lookup by path
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "debug"))]
+pub fn parse_acf_with_meta(path: &str) -> Result<(Acf, SystemTime)> {
+    let mut file = match fs::File::open(path) {
+        Ok(val) => val,
+        Err(e) => return Err(AcfError::reading(path, &e)),
+    };
+
+    let modified = match file.metadata().and_then(|meta| meta.modified()) {
         Ok(val) => val,
         Err(_) => return Err(AcfError::Read(path.into())),
     };
 
-    let entries = match acf_parser().parse(&contents).into_result() {
+    #[cfg(feature = "tracing")]
+    let started = std::time::Instant::now();
+
+    let mut contents = String::new();
+    let result = file.read_to_string(&mut contents);
+
+    #[cfg(feature = "tracing")]
+    trace::read(path, result.as_ref().copied(), started);
+
+    if result.is_err() {
+        return Err(AcfError::Read(path.into()));
+    }
+
+    Ok((parse_contents(&contents, &ParseOptions::default())?, modified))
+}
+
+/// Reads the whole file at the given path
+fn read_file(path: &str) -> Result<String> {
+    #[cfg(feature = "tracing")]
+    let started = std::time::Instant::now();
+
+    let result = fs::read_to_string(path);
+
+    #[cfg(feature = "tracing")]
+    trace::read(path, result.as_ref().map(String::len), started);
+
+    result.map_err(|e| AcfError::reading(path, &e))
+}
+
+/// Parses in-memory ACF content into an [`Acf`]
+fn parse_contents(src: &str, options: &ParseOptions) -> Result<Acf> {
+    #[cfg(feature = "tracing")]
+    let (span, started) = (trace::parse_span(src.len(), options), std::time::Instant::now());
+    #[cfg(feature = "tracing")]
+    let _entered = span.enter();
+
+    let result = parse_checked(src, options);
+
+    #[cfg(feature = "tracing")]
+    trace::parsed(&span, &result, started);
+
+    result
+}
+
+/// Enforces the options' limits, then runs the grammar over the input
+fn parse_checked(src: &str, options: &ParseOptions) -> Result<Acf> {
+    if let Some(limit) = options.max_input_bytes.filter(|&limit| src.len() > limit) {
+        let span = Span::new(limit, src.len());
+        let err = ParseError::limit_exceeded(Limit::InputBytes, limit, span);
+        return Err(AcfError::Parse(err));
+    }
+
+    let quote = options.quote_char;
+    check_depth(src, options.depth_limit(), quote).map_err(AcfError::Parse)?;
+
+    if options.has_limits() {
+        check_limits(src, options).map_err(AcfError::Parse)?;
+    }
+
+    if options.strict_escapes {
+        check_escapes(src, options).map_err(AcfError::Parse)?;
+    }
+
+    if options.on_duplicate == DuplicatePolicy::Error {
+        check_duplicates(src, options).map_err(AcfError::Parse)?;
+    }
+
+    let grammar = acf_parser(quote, options.unquoted_names);
+    let mut entries = match grammar.parse(src).into_result() {
         Ok(val) => val,
-        Err(e) => {
-            e.into_iter()
-                .for_each(|err| println!("Parse error: {}", err));
-            return Err(AcfError::Parse(ParseError::Unknown));
-        }
+        Err(errs) => return Err(AcfError::Parse(syntax_error(src, errs, options))),
     };
 
+    match options.on_duplicate {
+        DuplicatePolicy::KeepFirst => keep_unique_keys(&mut entries, false),
+        DuplicatePolicy::KeepLast => keep_unique_keys(&mut entries, true),
+        DuplicatePolicy::KeepAll | DuplicatePolicy::Error => {}
+    }
+
+    if options.capture_comments {
+        attach_comments(src, options, &mut entries);
+    }
+
     Ok(Acf { entries })
 }
 
+/// Nesting depth check
+///
+/// Rejects input whose braces nest deeper than `limit` before the recursive grammar can be
+/// driven into a stack overflow. Every opening brace is counted, so the check is conservative
+pub(crate) fn check_depth(
+    src: &str,
+    limit: usize,
+    quote: char,
+) -> std::result::Result<(), ParseError> {
+    let mut depth = 0usize;
+
+    for token in Lexer::with_quote(src, quote) {
+        match token.kind {
+            TokenKind::Open => {
+                depth += 1;
+                if depth > limit {
+                    return Err(ParseError::LimitExceeded {
+                        which: Limit::Depth,
+                        limit,
+                        span: Span::from(token.start..token.end),
+                    });
+                }
+            }
+            TokenKind::Close => depth = depth.saturating_sub(1),
+            TokenKind::Str | TokenKind::Invalid => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Comment capture
+///
+/// Walks the tokens of input the grammar has already accepted, attaching the `//` comments found
+/// between them to expressions as described for [`ParseOptions::capture_comments`]
+fn attach_comments(src: &str, options: &ParseOptions, entries: &mut [Entry]) {
+    // Index of each open entry within its parent, and the number of sub-entries seen so far at
+    // each level, starting with the root
+    let mut path = Vec::new();
+    let mut children = vec![0];
+
+    // Comments read since the last token, waiting for the next key
+    let mut pending = Vec::new();
+
+    let mut tokens = options.lexer(src).peekable();
+    let mut prev_end = 0;
+
+    while let Some(token) = tokens.next() {
+        pending.extend(line_comments(&src[prev_end..token.start]));
+        prev_end = token.end;
+
+        match token.kind {
+            TokenKind::Str => {}
+            TokenKind::Open => {
+                pending.clear();
+                continue;
+            }
+            TokenKind::Close => {
+                pending.clear();
+                path.pop();
+                children.pop();
+                continue;
+            }
+            TokenKind::Invalid => return,
+        }
+
+        let Some(value) = tokens.next_if(|next| next.kind == TokenKind::Str) else {
+            // An entry's name, followed by its opening brace
+            pending.clear();
+            let Some(index) = children.last_mut() else {
+                return;
+            };
+            path.push(*index);
+            *index += 1;
+            children.push(0);
+            continue;
+        };
+
+        // Comments between the key and value, then any on the rest of the value's line
+        pending.extend(line_comments(&src[token.end..value.start]));
+        let next_start = tokens.peek().map_or(src.len(), |next| next.start);
+        let rest = &src[value.end..next_start];
+        let line_len = rest.find('\n').unwrap_or(rest.len());
+        pending.extend(line_comments(&rest[..line_len]));
+        prev_end = value.end + line_len;
+
+        if pending.is_empty() {
+            continue;
+        }
+        let key = token.contents(src, options.quote_char);
+        if let Some(entry) = entry_at(entries, &path) {
+            entry.comments.push((unescape(key), pending.join("\n")));
+        }
+        pending.clear();
+    }
+}
+
+/// Returns the text of each `//` comment within whitespace between tokens, trimmed and without
+/// the leading slashes
+fn line_comments(gap: &str) -> Vec<&str> {
+    let mut comments = Vec::new();
+    let mut rest = gap;
+    while let Some(start) = rest.find("//") {
+        let comment = &rest[start + 2..];
+        let len = comment.find('\n').unwrap_or(comment.len());
+        comments.push(comment[..len].trim());
+        rest = &comment[len..];
+    }
+
+    comments
+}
+
+/// Returns the entry at the given path of sub-entry indices
+fn entry_at<'a>(entries: &'a mut [Entry], path: &[usize]) -> Option<&'a mut Entry> {
+    let (&first, rest) = path.split_first()?;
+    rest.iter()
+        .try_fold(entries.get_mut(first)?, |entry, &index| entry.entries.get_mut(index))
+}
+
+/// Converts the grammar's errors into a [`ParseError`]
+///
+/// Input that ends with entries left open is reported as a missing closing brace spanning from
+/// the outermost open entry to the end of the input. Anything else reports the first error
+pub(crate) fn syntax_error(
+    src: &str,
+    errs: Vec<Rich<'_, char>>,
+    options: &ParseOptions,
+) -> ParseError {
+    if let Some(start) = unclosed_brace(src, options) {
+        return ParseError::ExpectedClosingBrace(Span::from(start..src.len()));
+    }
+
+    match errs.into_iter().next() {
+        Some(err) => ParseError::Syntax {
+            span: Span::new(err.span().start, err.span().end),
+            message: err.to_string(),
+        },
+        None => ParseError::Unknown,
+    }
+}
+
+/// Returns the offset of the outermost opening brace left unclosed at the end of the input
+fn unclosed_brace(src: &str, options: &ParseOptions) -> Option<usize> {
+    let mut open = Vec::new();
+
+    for token in options.lexer(src) {
+        match token.kind {
+            TokenKind::Open => open.push(token.start),
+            TokenKind::Close => {
+                open.pop()?;
+            }
+            TokenKind::Str => {}
+            TokenKind::Invalid => return None,
+        }
+    }
+
+    open.first().copied()
+}
+
+/// Resource limit check
+///
+/// Walks the token stream counting entries and expressions, returning as soon as any limit in the
+/// options is exceeded. Nothing is allocated, so oversized input is rejected cheaply. Malformed
+/// input is left for the grammar to report
+fn check_limits(src: &str, options: &ParseOptions) -> std::result::Result<(), ParseError> {
+    let exceeds = |limit: Option<usize>, count: usize, which: Limit, span: Span| match limit {
+        Some(limit) if count > limit => Err(ParseError::LimitExceeded { which, limit, span }),
+        _ => Ok(()),
+    };
+
+    let mut keys = 0;
+    let mut entries = 0;
+    let mut pending = None;
+    let mut per_entry = vec![0];
+
+    for token in options.lexer(src) {
+        let span = Span::from(token.start..token.end);
+
+        match token.kind {
+            TokenKind::Str => {
+                let len = token.content_len(options.quote_char);
+                exceeds(options.max_value_len, len, Limit::ValueLength, span)?;
+
+                match pending.take() {
+                    Some(start) => {
+                        keys += 1;
+                        let span = Span::from(start..token.end);
+                        exceeds(options.max_total_keys, keys, Limit::TotalKeys, span)?;
+
+                        if let Some(count) = per_entry.last_mut() {
+                            *count += 1;
+                            let which = Limit::ExpressionsPerEntry;
+                            exceeds(options.max_expressions_per_entry, *count, which, span)?;
+                        }
+                    }
+                    None => pending = Some(token.start),
+                }
+            }
+            TokenKind::Open => {
+                if let Some(start) = pending.take() {
+                    entries += 1;
+                    let span = Span::from(start..token.end);
+                    exceeds(options.max_total_entries, entries, Limit::TotalEntries, span)?;
+                }
+                per_entry.push(0);
+            }
+            TokenKind::Close => {
+                pending = None;
+                if per_entry.len() > 1 {
+                    per_entry.pop();
+                }
+            }
+            TokenKind::Invalid => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Escape sequence check
+///
+/// Rejects the first backslash sequence within a string literal that isn't `\t`, `\n`, or
+/// `\\`. Malformed input is left for the grammar to report
+fn check_escapes(src: &str, options: &ParseOptions) -> std::result::Result<(), ParseError> {
+    let quote = options.quote_char;
+    for token in options.lexer(src) {
+        match token.kind {
+            TokenKind::Str if token.bare => continue,
+            TokenKind::Str => {}
+            TokenKind::Invalid => break,
+            TokenKind::Open | TokenKind::Close => continue,
+        }
+
+        let start = token.start + quote.len_utf8();
+        let contents = token.contents(src, quote);
+        let mut chars = contents.char_indices();
+
+        while let Some((offset, c)) = chars.next() {
+            if c != '\\' {
+                continue;
+            }
+
+            let offset = start + offset;
+            match chars.next() {
+                Some((_, 't' | 'n' | 'r' | '\\')) => {}
+                Some((_, c)) if c == quote => {}
+                Some((_, c)) => {
+                    let span = Span::new(offset, offset + 1 + c.len_utf8());
+                    let message = format!("unknown escape sequence '\\{}'", c);
+                    return Err(ParseError::syntax(span, message));
+                }
+                None => {
+                    let span = Span::new(offset, offset + 1);
+                    return Err(ParseError::syntax(span, "incomplete escape sequence"));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Duplicate key check
+///
+/// Rejects the first expression whose key was already used within the same entry. Malformed
+/// input is left for the grammar to report
+fn check_duplicates(src: &str, options: &ParseOptions) -> std::result::Result<(), ParseError> {
+    let mut pending: Option<Token> = None;
+    let mut seen = vec![HashSet::new()];
+
+    for token in options.lexer(src) {
+        match token.kind {
+            TokenKind::Str => match pending.take() {
+                Some(key) => {
+                    let name = unescape(key.contents(src, options.quote_char));
+                    if let Some(keys) = seen.last_mut() {
+                        if !keys.insert(name) {
+                            let span = Span::from(key.start..token.end);
+                            return Err(ParseError::DuplicateKey(span));
+                        }
+                    }
+                }
+                None => pending = Some(token),
+            },
+            TokenKind::Open => {
+                pending = None;
+                seen.push(HashSet::new());
+            }
+            TokenKind::Close => {
+                pending = None;
+                if seen.len() > 1 {
+                    seen.pop();
+                }
+            }
+            TokenKind::Invalid => break,
+        }
+    }
+
+    Ok(())
+}
+
+/// Recursively drops repeated keys from every entry, keeping either the first or the last
+/// expression with each key
+fn keep_unique_keys(entries: &mut [Entry], keep_last: bool) {
+    for entry in entries {
+        let mut expressions = std::mem::take(&mut entry.expressions)
+            .into_iter()
+            .collect::<Vec<_>>();
+        if keep_last {
+            expressions.reverse();
+        }
+
+        let mut seen = HashSet::new();
+        expressions.retain(|(key, _)| seen.insert(key.clone()));
+
+        if keep_last {
+            expressions.reverse();
+        }
+        entry.expressions = expressions.into_iter().collect();
+
+        keep_unique_keys(&mut entry.entries, keep_last);
+    }
+}
+
 /// ACF parser
 ///
 /// A wrapper for the entry parser that allows multiple entries to be defined within the file.
 /// Will parse until the end of the file is reached
-fn acf_parser<'src>() -> impl Parser<'src, &'src str, Vec<Entry>> {
-    entry_parser()
-        .padded()
+fn acf_parser<'src>(
+    quote: char,
+    unquoted_names: bool,
+) -> impl Parser<'src, &'src str, Vec<Entry>, Extra<'src>> {
+    entry_parser(quote, unquoted_names)
+        .padded_by(whitespace())
         .repeated()
         .collect::<Vec<_>>()
         .then_ignore(end())
@@ -79,56 +857,164 @@ fn acf_parser<'src>() -> impl Parser<'src, &'src str, Vec<Entry>> {
 
 /// Entry parser
 ///
-/// Entries start with a string literal followed by an opening brace (i.e., '{'). Entries are
-/// expected to have a list of expressions, followed by a list of sub-entries. This ordering
-/// is currently enforced
-fn entry_parser<'src>() -> impl Parser<'src, &'src str, Entry> {
-    recursive(|rec_parser| {
-        str_parser()
-            .padded()
-            .then_ignore(just("{").padded())
+/// Entries start with a name followed by an opening brace (i.e., '{'). Entries are expected to
+/// have a list of expressions, followed by a list of sub-entries. This ordering is currently
+/// enforced
+pub(crate) fn entry_parser<'src>(
+    quote: char,
+    unquoted_names: bool,
+) -> impl Parser<'src, &'src str, Entry, Extra<'src>> {
+    recursive(move |rec_parser| {
+        name_parser(quote, unquoted_names)
+            .padded_by(whitespace())
+            .then_ignore(just("{").padded_by(whitespace()))
             .then(
-                expr_parser().padded().repeated().collect::<Vec<_>>()
+                expr_parser(quote).padded_by(whitespace()).repeated().collect::<Vec<_>>()
             )
-            .then(rec_parser.padded().repeated().collect::<Vec<_>>())
-            .then_ignore(just("}").padded())
+            .then(rec_parser.padded_by(whitespace()).repeated().collect::<Vec<_>>())
+            .then_ignore(just("}").padded_by(whitespace()))
             .map(|((name, expressions), entries)| Entry {
                 name,
-                expressions: {
-                    let names = expressions.iter().map(|expr| expr.name.clone());
-                    let values = expressions.iter().map(|expr| expr.value.clone());
-
-                    names.zip(values).collect()
-                },
+                expressions: expressions
+                    .into_iter()
+                    .map(|expr| (expr.name, expr.value))
+                    .collect(),
                 entries,
+                comments: Vec::new(),
             })
             .boxed()
     })
 }
 
+/// Entry name parser
+///
+/// Names are string literals, or with `unquoted` also bare words running up to the next
+/// whitespace, quote, or brace
+fn name_parser<'src>(
+    quote: char,
+    unquoted: bool,
+) -> impl Parser<'src, &'src str, String, Extra<'src>> + Clone {
+    if !unquoted {
+        return str_parser(quote).boxed();
+    }
+
+    let bare = any()
+        .filter(move |c: &char| is_bare_char(*c, quote))
+        .repeated()
+        .at_least(1)
+        .to_slice()
+        .map(String::from)
+        .padded_by(whitespace());
+    str_parser(quote).or(bare).boxed()
+}
+
 /// Expression parser
 ///
 /// Expressions are formed by two string literals delimited by some whitespace. There are no
 /// constraints as to what may form entries (will match up until next quote), so you may get
 /// strange resulting expressions if the input file is incorrectly formatted
-fn expr_parser<'src>() -> impl Parser<'src, &'src str, Expr> {
-    str_parser()
-        .padded()
-        .then(str_parser())
-        .padded()
+pub(crate) fn expr_parser<'src>(quote: char) -> impl Parser<'src, &'src str, Expr, Extra<'src>> {
+    str_parser(quote)
+        .padded_by(whitespace())
+        .then(str_parser(quote))
+        .padded_by(whitespace())
         .map(|(str1, str2)| Expr {
             name: str1,
             value: str2,
         })
 }
 
+/// Whitespace parser
+///
+/// Skips the separators VDF allows between tokens: spaces, tabs, carriage returns, line feeds,
+/// vertical tabs, form feeds, and `//` line comments. This is deliberately narrower than
+/// chumsky's default padding, which accepts any Unicode whitespace
+pub(crate) fn whitespace<'src>() -> impl Parser<'src, &'src str, (), Extra<'src>> + Clone {
+    let comment = just("//").then(none_of('\n').repeated()).ignored();
+
+    one_of(WHITESPACE).ignored().or(comment).repeated()
+}
+
 /// String literal parser
-fn str_parser<'src>() -> impl Parser<'src, &'src str, String> {
-    just('"')
-        .ignore_then(none_of('"').repeated().to_slice())
-        .then_ignore(just('"'))
-        .padded()
-        .map(|val: &str| val.to_owned())
+///
+/// Strings are delimited by `quote` and run up to the next occurrence of it that isn't escaped
+/// with a backslash
+pub(crate) fn str_parser<'src>(quote: char) -> impl Parser<'src, &'src str, String, Extra<'src>> {
+    literal_parser(quote).padded_by(whitespace()).map(unescape)
+}
+
+/// String literal contents parser
+///
+/// Matches a string literal without decoding it, returning the text between the quotes. A
+/// backslash always takes the following character with it, so `\"` doesn't end the string
+pub(crate) fn literal_parser<'src>(
+    quote: char,
+) -> impl Parser<'src, &'src str, &'src str, Extra<'src>> + Clone {
+    let escaped = just('\\').then(any()).ignored();
+    let plain = none_of([quote, '\\']).ignored();
+
+    just(quote)
+        .ignore_then(plain.or(escaped).repeated().to_slice())
+        .then_ignore(just(quote))
+}
+
+/// Decodes `\t` escape sequences within a string literal into tabs
+///
+/// Any other backslash sequence, including `\\`, is kept exactly as written
+fn unescape(val: &str) -> String {
+    if !val.contains('\\') {
+        return val.to_owned();
+    }
+
+    let mut out = String::with_capacity(val.len());
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some(next) => {
+                out.push('\\');
+                out.push(next);
+            }
+            None => out.push('\\'),
+        }
+    }
+
+    out
+}
+
+/// Expands `\n`, `\r`, `\\`, and `\"` escape sequences, borrowing the string if there are none
+fn expand_escapes(val: &str) -> Cow<'_, str> {
+    if !["\\n", "\\r", "\\\\", "\\\""].iter().any(|seq| val.contains(seq)) {
+        return Cow::Borrowed(val);
+    }
+
+    let mut out = String::with_capacity(val.len());
+    let mut chars = val.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('\\') => out.push('\\'),
+            Some('"') => out.push('"'),
+            Some(next) => {
+                out.push('\\');
+                out.push(next);
+            }
+            None => out.push('\\'),
+        }
+    }
+
+    Cow::Owned(out)
 }
 
 #[cfg(test)]
@@ -152,6 +1038,54 @@ mod tests {
         assert_eq!(expressions["appid"], "730");
     }
 
+    #[test]
+    fn no_trailing_newline() {
+        let src = fs::read_to_string("./acfs/no_trailing_newline.acf").unwrap();
+        assert!(src.ends_with('}'));
+
+        let result = parse_acf("./acfs/no_trailing_newline.acf").unwrap();
+        assert_eq!(result, parse_acf("./acfs/simple.acf").unwrap());
+
+        for src in ["\"A\"{\"k\"\"v\"}", "\"A\" { } // trailing comment", "\"A\"\n{\n}\t"] {
+            assert_eq!(parse_acf_str(src).unwrap().entries[0].name, "A");
+        }
+    }
+
+    #[test]
+    fn braces_in_quoted_values() {
+        let src = "\"Root\"{\"pattern\"\"{foo}\"\"close\"\"}\"\"Child\"{\"open\"\"{\"}}";
+        let result = parse_acf_str(src).unwrap();
+        let root_entry = &result.entries[0];
+        assert_eq!(root_entry.expressions["pattern"], "{foo}");
+        assert_eq!(root_entry.expressions["close"], "}");
+        assert_eq!(root_entry.entries.len(), 1);
+        assert_eq!(root_entry.entries[0].expressions["open"], "{");
+    }
+
+    #[test]
+    fn is_valid_acf() {
+        let src = fs::read_to_string("./acfs/appmanifest_730.acf").unwrap();
+        assert!(super::is_valid_acf(&src));
+        assert!(super::is_valid_acf(""));
+        assert!(super::is_valid_acf("\"A\" { \"k\" \"v\" } // comment"));
+
+        assert!(!super::is_valid_acf(&src[..src.len() - 2]));
+        assert!(!super::is_valid_acf("\"A\" { \"k\" }"));
+        assert!(!super::is_valid_acf("\"A\" { junk }"));
+        assert!(!super::is_valid_acf("not an acf"));
+    }
+
+    #[test]
+    fn key_order() {
+        let result = parse_acf("./acfs/simple.acf").unwrap();
+        let keys = ["appid", "universe", "LauncherPath", "name", "StateFlags", "installdir"];
+        assert_eq!(result.entries[0].key_order(), keys);
+
+        let result = parse_acf("./acfs/duplicate_keys.acf").unwrap();
+        let keys = ["appid", "name", "StateFlags", "StateFlags"];
+        assert_eq!(result.entries[0].key_order(), keys);
+    }
+
     #[test]
     fn full() {
         let result = parse_acf("./acfs/appmanifest_730.acf");
@@ -165,4 +1099,440 @@ mod tests {
         assert_eq!(expressions["LauncherPath"], "C:\\\\Program Files (x86)\\\\Steam\\\\steam.exe");
         assert_eq!(expressions["name"], "Counter-Strike 2");
     }
+
+    #[test]
+    fn child_names() {
+        let result = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let root_entry = &result.entries[0];
+        assert_eq!(
+            root_entry.child_names(),
+            ["InstalledDepots", "SharedDepots", "UserConfig", "MountedConfig"]
+        );
+        assert!(root_entry.entries[2].child_names().is_empty());
+    }
+
+    #[test]
+    fn child_index() {
+        let result = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let root_entry = &result.entries[0];
+        assert_eq!(root_entry.child_index("UserConfig"), Some(2));
+        assert_eq!(root_entry.child_index("InstalledDepots"), Some(0));
+        assert_eq!(root_entry.entries[0].child_index("2347779"), Some(6));
+        assert_eq!(root_entry.child_index("userconfig"), None);
+        assert_eq!(root_entry.child_index("appid"), None);
+    }
+
+    #[test]
+    fn total_expression_count() {
+        let result = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let root_entry = &result.entries[0];
+        assert_eq!(root_entry.total_expression_count(), 44);
+        assert_eq!(root_entry.entries[0].total_expression_count(), 15);
+        assert_eq!(Entry::default().total_expression_count(), 0);
+    }
+
+    #[test]
+    fn multiple_documents() {
+        let src = concat!(
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n}\n\x0C",
+            "\"AppState\"\n{\n\t\"name\"\t\t\"page\x0Cbreak\"\n}\n\x0C\n",
+        );
+        let results = parse_acf_multi(src);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].as_ref().unwrap().entries[0].expressions["appid"], "730");
+        assert_eq!(results[1].as_ref().unwrap().entries[0].expressions["name"], "page\x0Cbreak");
+
+        let results = parse_acf_multi("\"A\" { }\x0C\"B\" {\x0C\"C\" { \"k\" \"v\" }");
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert_eq!(results[2].as_ref().unwrap().entries[0].name, "C");
+        assert!(parse_acf_multi("\x0C \n").is_empty());
+    }
+
+    #[test]
+    fn first_value() {
+        let result = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let root_entry = &result.entries[0];
+        assert_eq!(root_entry.first_value(), Some("730"));
+        assert_eq!(root_entry.entries[1].first_value(), Some("228980"));
+        assert_eq!(root_entry.entries[0].first_value(), None);
+    }
+
+    #[test]
+    fn get_multiline() {
+        let src = "\"Root\"\n{\n\t\"notes\"\t\t\"first line\n\tsecond line\r\nthird\n\"\n}\n";
+        let result = parse_acf_str(src).unwrap();
+        let root_entry = &result.entries[0];
+        assert_eq!(
+            root_entry.get_multiline("notes"),
+            Some("first line\n\tsecond line\r\nthird\n")
+        );
+        assert_eq!(root_entry.get_multiline("missing"), None);
+    }
+
+    #[test]
+    fn get_cow() {
+        let result = parse_acf("./acfs/simple.acf").unwrap();
+        let root_entry = &result.entries[0];
+        assert!(matches!(root_entry.get_cow("name"), Some(Cow::Borrowed("Counter-Strike 2"))));
+
+        let path = root_entry.get_cow("LauncherPath").unwrap();
+        assert!(matches!(path, Cow::Owned(_)));
+        assert_eq!(path, r"C:\Program Files (x86)\Steam\steam.exe");
+        assert_eq!(root_entry.get_cow("missing"), None);
+
+        let src = r#""Root" { "notes" "a\nb\\c\q" "path" "C:\Steam" }"#;
+        let result = parse_acf_str(src).unwrap();
+        let root_entry = &result.entries[0];
+        assert_eq!(root_entry.get_cow("notes").unwrap(), "a\nb\\c\\q");
+        assert!(matches!(root_entry.get_cow("path"), Some(Cow::Borrowed(r"C:\Steam"))));
+    }
+
+    #[test]
+    fn comment_for() {
+        let src = concat!(
+            "// Generated by Steam\n",
+            "\"AppState\"\n{\n",
+            "\t\"appid\"\t\t\"730\"\t// the app's ID\n",
+            "\t// Install state bit flags\n",
+            "\t// 4 means fully installed\n",
+            "\t\"StateFlags\"\t\t\"4\"\n",
+            "\t\"name\"\t\t\"Counter-Strike 2\"\n",
+            "\t// no key follows\n",
+            "\t\"UserConfig\"\n\t{\n",
+            "\t\t\"language\"\t\t\"english\" //see http://example.com\n",
+            "\t}\n}\n",
+        );
+        let options = ParseOptions {
+            capture_comments: true,
+            ..Default::default()
+        };
+        let result = parse_acf_str_with_options(src, &options).unwrap();
+        let root_entry = &result.entries[0];
+
+        assert_eq!(root_entry.comment_for("appid"), Some("the app's ID"));
+        assert_eq!(
+            root_entry.comment_for("StateFlags"),
+            Some("Install state bit flags\n4 means fully installed")
+        );
+        assert_eq!(root_entry.comment_for("name"), None);
+        assert_eq!(root_entry.comments.len(), 2);
+        let user_config = &root_entry.entries[0];
+        assert_eq!(
+            user_config.comment_for("language"),
+            Some("see http://example.com")
+        );
+
+        let plain = parse_acf_str(src).unwrap();
+        assert_eq!(plain.entries[0].comment_for("appid"), None);
+        assert_eq!(plain, result);
+
+        let mut root_entry = plain.entries[0].clone();
+        assert_eq!(root_entry.set_comment("name", "display name"), None);
+        assert_eq!(root_entry.set_comment("name", "title"), Some("display name".into()));
+        assert_eq!(root_entry.comment_for("name"), Some("title"));
+    }
+
+    #[test]
+    fn to_map() {
+        let result = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let map = result.entries[0].to_map();
+        assert_eq!(map.len(), result.entries[0].expressions.len());
+        assert_eq!(map["appid"], "730");
+        assert_eq!(map["name"], "Counter-Strike 2");
+        assert_eq!(map["buildid"], "20040392");
+
+        let acf = parse_acf_str("\"Root\" { \"key\" \"first\" \"key\" \"second\" }").unwrap();
+        assert_eq!(acf.entries[0].to_map()["key"], "first");
+    }
+
+    #[test]
+    fn reader_progress() {
+        let file = fs::File::open("./acfs/appmanifest_730.acf").unwrap();
+        let reader = std::io::BufReader::with_capacity(64, file);
+
+        let mut progress = Vec::new();
+        let acf = parse_acf_reader_progress(reader, |bytes| progress.push(bytes)).unwrap();
+        assert_eq!(acf, parse_acf("./acfs/appmanifest_730.acf").unwrap());
+
+        let len = fs::metadata("./acfs/appmanifest_730.acf").unwrap().len() as usize;
+        assert!(progress.len() > 1);
+        assert!(progress.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(progress.last(), Some(&len));
+    }
+
+    #[test]
+    fn with_meta() {
+        let path = std::env::temp_dir().join(format!("acf-parser-meta-{}.acf", std::process::id()));
+        fs::copy("./acfs/simple.acf", &path).unwrap();
+
+        let result = parse_acf_with_meta(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        let (acf, modified) = result.unwrap();
+        assert_eq!(acf, parse_acf("./acfs/simple.acf").unwrap());
+        let age = SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default();
+        assert!(age < std::time::Duration::from_secs(60 * 60));
+    }
+
+    #[test]
+    fn missing_file() {
+        let err = parse_acf("./acfs/missing.acf").unwrap_err();
+        assert!(err.is_not_found());
+        assert_eq!(err, AcfError::not_found("./acfs/missing.acf"));
+
+        let err = parse_acf("./acfs/missing.acf").unwrap_err();
+        assert!(err.is_not_found());
+        assert!(parse_acf_with_meta("./acfs/missing.acf").unwrap_err().is_not_found());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("acf-parser-mode-{}.acf", std::process::id()));
+        fs::copy("./acfs/simple.acf", &path).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Privileged users (e.g. root in a container) can read the file regardless
+        let readable = fs::File::open(&path).is_ok();
+        let result = parse_acf(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+
+        if !readable {
+            let err = result.unwrap_err();
+            assert!(err.is_read());
+            assert!(!err.is_not_found());
+        }
+    }
+
+    #[test]
+    fn limits_not_exceeded() {
+        let options = ParseOptions {
+            max_total_keys: Some(50),
+            max_total_entries: Some(20),
+            max_value_len: Some(64),
+            max_depth: Some(3),
+            ..Default::default()
+        };
+        let result = parse_acf_with_options("./acfs/appmanifest_730.acf", &options);
+        assert_eq!(result.unwrap(), parse_acf("./acfs/appmanifest_730.acf").unwrap());
+    }
+
+    #[test]
+    fn limits_exceeded() {
+        let options = ParseOptions {
+            max_total_entries: Some(4),
+            ..Default::default()
+        };
+        let err = parse_acf_with_options("./acfs/appmanifest_730.acf", &options).unwrap_err();
+        match err.as_parse_error() {
+            Some(ParseError::LimitExceeded { which, limit, .. }) => {
+                assert_eq!(*which, Limit::TotalEntries);
+                assert_eq!(*limit, 4);
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn syntax_error_position() {
+        let src = "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n\tjunk\n}\n";
+        let (result, index) = parse_acf_str_indexed(src);
+        let err = result.unwrap_err();
+        let err = err.as_parse_error().unwrap();
+        assert!(err.is_syntax());
+        assert_eq!(index.line_col(err.span().unwrap().start), (4, 2));
+    }
+
+    #[test]
+    fn syntax_error_position_multibyte() {
+        let src = "\"AppState\"\n{\n\t\"name\"\t\t\"Ōkami — 大神\" junk\n}\n";
+        let (result, index) = parse_acf_str_indexed(src);
+        let err = result.unwrap_err();
+        let span = err.as_parse_error().unwrap().span().unwrap();
+        assert_eq!(span.start, src.find("junk").unwrap());
+
+        // The quoted name is 12 characters but 19 bytes, so counting bytes would give column 30
+        assert_eq!(index.line_col(span.start), (3, 23));
+    }
+
+    #[test]
+    fn missing_closing_brace() {
+        let src = "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n";
+        let err = parse_acf_str(src).unwrap_err();
+        let err = err.as_parse_error().unwrap();
+        assert!(err.is_expected_closing_brace());
+        assert_eq!(err.span(), Some(Span::from(11..src.len())));
+    }
+
+    #[test]
+    fn depth_limit() {
+        let options = ParseOptions {
+            max_depth: Some(2),
+            ..Default::default()
+        };
+        let err = parse_acf_with_options("./acfs/appmanifest_730.acf", &options).unwrap_err();
+        let err = err.as_parse_error().unwrap();
+        assert!(err.is_limit_exceeded());
+
+        let src = "\"a\"{".repeat(DEFAULT_MAX_DEPTH + 1);
+        assert!(parse_acf_str(&src).is_err());
+    }
+
+    #[test]
+    fn vdf_whitespace() {
+        let src = "// comment\n\x0C\"AppState\"\x0C{\x0B\"appid\"\x0B\"730\"\r\n}\x0C";
+        let acf = parse_acf_str(src).unwrap();
+        assert_eq!(acf.entries.len(), 1);
+        assert_eq!(acf.entries[0].expressions["appid"], "730");
+
+        let src = "\"AppState\" { \"appid\" \"730\" // trailing \"comment\" }\n}";
+        assert_eq!(acf, parse_acf_str(src).unwrap());
+
+        let src = "\"AppState\"\u{A0}{ \"appid\" \"730\" }";
+        assert!(parse_acf_str(src).unwrap_err().as_parse_error().unwrap().is_syntax());
+    }
+
+    #[test]
+    fn brace_placement() {
+        let expected = parse_acf_str(concat!(
+            "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n",
+            "\t\"UserConfig\"\n\t{\n\t\t\"language\"\t\t\"english\"\n\t}\n}\n",
+        ))
+        .unwrap();
+
+        for src in [
+            concat!(
+                "\"AppState\" {\n\t\"appid\" \"730\"\n",
+                "\t\"UserConfig\" {\n\t\t\"language\" \"english\"\n\t}\n}\n",
+            ),
+            "\"AppState\"{\"appid\"\"730\"\"UserConfig\"{\"language\"\"english\"}}",
+            "\"AppState\"\t{ \"appid\" \"730\" \"UserConfig\"\r\n{ \"language\" \"english\" } }",
+            concat!(
+                "\"AppState\" // comment\n{\n\"appid\" \"730\"\n",
+                "\"UserConfig\" { \"language\" \"english\" }\n}",
+            ),
+        ] {
+            assert_eq!(parse_acf_str(src).unwrap(), expected, "{:?}", src);
+            let lazy = crate::lazy::parse_acf_lazy(src).unwrap();
+            assert_eq!(lazy.to_acf().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn single_quotes() {
+        let options = ParseOptions {
+            quote_char: '\'',
+            ..Default::default()
+        };
+        let src = "'AppState'\n{\n\t'appid'\t\t'730'\n\t'name'\t\t'say \"hi\"'\n}\n";
+        let acf = parse_acf_str_with_options(src, &options).unwrap();
+        assert_eq!(acf.entries[0].name, "AppState");
+        assert_eq!(acf.entries[0].expressions["appid"], "730");
+        assert_eq!(acf.entries[0].expressions["name"], "say \"hi\"");
+
+        assert!(parse_acf_str(src).is_err());
+    }
+
+    #[test]
+    fn duplicate_policies() {
+        let src = fs::read_to_string("./acfs/duplicate_keys.acf").unwrap();
+        let parse = |on_duplicate| {
+            let options = ParseOptions {
+                on_duplicate,
+                ..ParseOptions::default()
+            };
+            parse_acf_str_with_options(&src, &options)
+        };
+
+        let acf = parse(DuplicatePolicy::KeepAll).unwrap();
+        assert_eq!(acf, parse_acf_str(&src).unwrap());
+        assert_eq!(acf.entries[0].key_order(), ["appid", "name", "StateFlags", "StateFlags"]);
+
+        let acf = parse(DuplicatePolicy::KeepFirst).unwrap();
+        let depot = &acf.entries[0].entries[0].entries[0];
+        assert_eq!(acf.entries[0].key_order(), ["appid", "name", "StateFlags"]);
+        assert_eq!(acf.entries[0].expressions["StateFlags"], "4");
+        assert_eq!(depot.key_order(), ["manifest", "size"]);
+        assert_eq!(depot.expressions["size"], "8");
+
+        let acf = parse(DuplicatePolicy::KeepLast).unwrap();
+        let depot = &acf.entries[0].entries[0].entries[0];
+        assert_eq!(acf.entries[0].key_order(), ["appid", "name", "StateFlags"]);
+        assert_eq!(acf.entries[0].expressions["StateFlags"], "6");
+        assert_eq!(depot.key_order(), ["manifest", "size"]);
+        assert_eq!(depot.expressions["size"], "24");
+        assert!(acf.duplicate_keys().is_empty());
+
+        let err = parse(DuplicatePolicy::Error).unwrap_err();
+        let err = err.as_parse_error().unwrap();
+        assert!(err.is_duplicate_key());
+        assert_eq!(&src[err.span().unwrap().range()], "\"StateFlags\"\t\t\"6\"");
+
+        let options = ParseOptions {
+            on_duplicate: DuplicatePolicy::Error,
+            ..ParseOptions::default()
+        };
+        let src = r#""A" { "k" "1" "B" { "k" "2" } "C" { "k" "3" } } "D" { "k" "4" }"#;
+        assert!(parse_acf_str_with_options(src, &options).is_ok());
+    }
+
+    #[test]
+    fn unquoted_names() {
+        let src = "AppState { \"appid\" \"730\" UserConfig{ \"language\" \"english\" } }";
+        assert!(parse_acf_str(src).is_err());
+
+        let options = ParseOptions {
+            unquoted_names: true,
+            ..Default::default()
+        };
+        let parse = |src| parse_acf_str_with_options(src, &options);
+        let acf = parse(src).unwrap();
+        let quoted = concat!(
+            "\"AppState\" { \"appid\" \"730\" ",
+            "\"UserConfig\" { \"language\" \"english\" } }",
+        );
+        assert_eq!(acf, parse_acf_str(quoted).unwrap());
+        assert_eq!(parse(quoted).unwrap(), acf);
+
+        assert!(parse("AppState { appid \"730\" }").is_err());
+        let err = parse("AppState { \"appid\" \"730\"").unwrap_err();
+        assert!(err.as_parse_error().unwrap().is_expected_closing_brace());
+
+        let options = ParseOptions {
+            max_total_entries: Some(1),
+            ..options
+        };
+        let err = parse_acf_str_with_options(src, &options).unwrap_err();
+        assert!(err.as_parse_error().unwrap().is_limit_exceeded());
+    }
+
+    #[test]
+    fn strict_escapes() {
+        let src = "\"AppState\" { \"path\" \"C:\\\\tools\\tbin\\q\" }";
+
+        let acf = parse_acf_str(src).unwrap();
+        assert_eq!(acf.entries[0].expressions["path"], "C:\\\\tools\tbin\\q");
+
+        let options = ParseOptions {
+            strict_escapes: true,
+            ..Default::default()
+        };
+        let err = parse_acf_str_with_options(src, &options).unwrap_err();
+        match err.as_parse_error() {
+            Some(ParseError::Syntax { span, message }) => {
+                assert_eq!(&src[span.range()], "\\q");
+                assert_eq!(message, "unknown escape sequence '\\q'");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+
+        let src = "\"AppState\" { \"path\" \"C:\\\\tools\\tbin\\n\\r\\\"\" }";
+        assert!(parse_acf_str_with_options(src, &options).is_ok());
+    }
 }
@@ -0,0 +1,532 @@
+use crate::expressions::Iter;
+use crate::parser::{Acf, Entry};
+use std::collections::{BTreeSet, HashSet};
+
+impl Acf {
+    /// Returns every entry in the tree that has an expression with the given key
+    ///
+    /// Entries are visited depth first in source order, so parents precede their sub-entries
+    pub fn entries_with_key(&self, key: &str) -> Vec<&Entry> {
+        let mut found = Vec::new();
+        collect(&self.entries, &mut |entry| entry.expressions.contains_key(key), &mut found);
+
+        found
+    }
+
+    /// Returns the first entry in the tree matching the predicate
+    ///
+    /// Entries are visited in the same depth first order as [`Acf::entries_with_key`], and the
+    /// search stops at the first match
+    pub fn find_first(&self, f: impl Fn(&Entry) -> bool) -> Option<&Entry> {
+        find_first(&self.entries, &f)
+    }
+
+    /// Returns the first root entry whose `appid` expression holds the given app ID
+    ///
+    /// Useful for documents holding several manifests, e.g. after [`Acf::append`]. Values that
+    /// aren't numbers are skipped
+    pub fn find_by_appid(&self, appid: u64) -> Option<&Entry> {
+        self.entries.iter().find(|entry| has_appid(entry, appid))
+    }
+
+    /// Returns the first root entry whose `appid` expression holds the given app ID, for editing
+    ///
+    /// Matches the same entry as [`Acf::find_by_appid`]
+    pub fn find_by_appid_mut(&mut self, appid: u64) -> Option<&mut Entry> {
+        self.entries.iter_mut().find(|entry| has_appid(entry, appid))
+    }
+
+    /// Returns the number of entries in the tree, counting the root entries and every sub-entry
+    pub fn entry_count(&self) -> usize {
+        self.entries.iter().map(entry_count).sum()
+    }
+
+    /// Returns the number of expressions in the tree, across every entry
+    pub fn expression_count(&self) -> usize {
+        self.entries.iter().map(Entry::total_expression_count).sum()
+    }
+
+    /// Sums the values of the given key across every entry in the tree, e.g. `SizeOnDisk` over
+    /// several manifests joined with [`Acf::append`]
+    ///
+    /// Each entry contributes the first value with the key, as returned by [`Expressions::get`].
+    /// Entries without the key, or whose value isn't a `u64`, are skipped. The sum saturates at
+    /// `u64::MAX` rather than overflowing
+    ///
+    /// [`Expressions::get`]: crate::expressions::Expressions::get
+    pub fn sum_u64(&self, key: &str) -> u64 {
+        self.entries_with_key(key)
+            .into_iter()
+            .filter_map(|entry| entry.expressions.get(key)?.parse::<u64>().ok())
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Returns every entry in the tree with the given name, at any depth (e.g. every depot
+    /// section, wherever it's nested)
+    ///
+    /// Entries are visited in the same depth first order as [`Acf::entries_with_key`]. Names are
+    /// compared exactly; use [`Acf::entries_named`] to ignore case or to learn where each match is
+    pub fn find_entries_by_name(&self, name: &str) -> Vec<&Entry> {
+        let mut found = Vec::new();
+        collect(&self.entries, &mut |entry| entry.name == name, &mut found);
+
+        found
+    }
+
+    /// Returns every entry in the tree with the given name, along with the names of its ancestors
+    ///
+    /// Entries are visited in the same depth first order as [`Acf::entries_with_key`], so an entry
+    /// is listed before any matches nested within it. With `ignore_case`, names are compared
+    /// ignoring ASCII case
+    pub fn entries_named(&self, name: &str, ignore_case: bool) -> Vec<(Vec<String>, &Entry)> {
+        let mut found = Vec::new();
+        entries_named(&self.entries, name, ignore_case, &mut Vec::new(), &mut found);
+
+        found
+    }
+
+    /// Calls `f` with every entry in the tree with the given name, along with the names of its
+    /// ancestors
+    ///
+    /// Matches are visited as in [`Acf::entries_named`]. Each entry is passed to `f` before its
+    /// sub-entries are searched, so changes to an entry's sub-entries affect which matches follow
+    pub fn entries_named_mut(
+        &mut self,
+        name: &str,
+        ignore_case: bool,
+        mut f: impl FnMut(&[String], &mut Entry),
+    ) {
+        entries_named_mut(&mut self.entries, name, ignore_case, &mut Vec::new(), &mut f);
+    }
+
+    /// Returns every entry nested exactly `depth` levels deep, where depth 0 is the root entries
+    ///
+    /// Entries are listed in source order, with an entry's sub-entries grouped after those of the
+    /// entries before it (e.g. depth 2 of a manifest lists each depot under `InstalledDepots`)
+    pub fn entries_at_depth(&self, depth: usize) -> Vec<&Entry> {
+        let mut layer = self.entries.iter().collect::<Vec<_>>();
+        for _ in 0..depth {
+            layer = layer.into_iter().flat_map(|entry| &entry.entries).collect();
+        }
+
+        layer
+    }
+
+    /// Returns the slash separated path of every expression in the tree (e.g.
+    /// `AppState/InstalledDepots/731/size`)
+    ///
+    /// Paths are listed depth first in source order, with an entry's own expressions before those
+    /// of its sub-entries. Repeated names produce repeated paths
+    pub fn leaf_paths(&self) -> Vec<String> {
+        let mut paths = Vec::new();
+        self.entries
+            .iter()
+            .for_each(|entry| leaf_paths(entry, &entry.name, &mut paths));
+
+        paths
+    }
+
+    /// Returns the set of slash separated expression paths in the tree, as listed by
+    /// [`Acf::leaf_paths`]
+    ///
+    /// Values are ignored, so set operations between two documents show which keys were added or
+    /// removed (e.g. `old.key_set().difference(&new.key_set())`)
+    pub fn key_set(&self) -> BTreeSet<String> {
+        self.leaf_paths().into_iter().collect()
+    }
+
+    /// Iterates over every expression in the tree as `(entry path, key, value)`, where the path is
+    /// slash separated (e.g. `AppState/InstalledDepots/731`)
+    ///
+    /// Expressions are visited in the same order as [`Acf::leaf_paths`], and the tree is walked
+    /// lazily as the iterator advances
+    pub fn iter_leaves(&self) -> impl Iterator<Item = (String, &str, &str)> {
+        Leaves {
+            pending: self.entries.iter().rev().map(|entry| (entry.name.clone(), entry)).collect(),
+            current: None,
+        }
+    }
+
+    /// Returns the slash separated path and key of every expression whose key appears more than
+    /// once within its entry (e.g. `("AppState/InstalledDepots/731", "size")`)
+    ///
+    /// Such keys lose data when read into a map, as only one value survives. Each duplicated key is
+    /// listed once per entry, in the order the entries and keys first appear
+    pub fn duplicate_keys(&self) -> Vec<(String, String)> {
+        let mut found = Vec::new();
+        self.entries
+            .iter()
+            .for_each(|entry| duplicate_keys(entry, &entry.name, &mut found));
+
+        found
+    }
+}
+
+/// Depth first walk over the expressions of a tree
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct Leaves<'a> {
+    /// Entries still to visit along with their paths, with the next entry last
+    pending: Vec<(String, &'a Entry)>,
+
+    /// Path and remaining expressions of the entry being visited
+    current: Option<(String, Iter<'a>)>,
+}
+
+impl<'a> Iterator for Leaves<'a> {
+    type Item = (String, &'a str, &'a str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((path, expressions)) = &mut self.current {
+                if let Some((key, value)) = expressions.next() {
+                    return Some((path.clone(), key.as_str(), value.as_str()));
+                }
+            }
+
+            let (path, entry) = self.pending.pop()?;
+            for sub_entry in entry.entries.iter().rev() {
+                self.pending.push((format!("{}/{}", path, sub_entry.name), sub_entry));
+            }
+            self.current = Some((path, entry.expressions.iter()));
+        }
+    }
+}
+
+/// Counts an entry along with all of its sub-entries, recursively
+fn entry_count(entry: &Entry) -> usize {
+    1 + entry.entries.iter().map(entry_count).sum::<usize>()
+}
+
+/// Collects the paths of the entry's expressions, then recurses into its sub-entries
+fn leaf_paths(entry: &Entry, prefix: &str, paths: &mut Vec<String>) {
+    paths.extend(entry.expressions.keys().map(|key| format!("{}/{}", prefix, key)));
+
+    for sub_entry in &entry.entries {
+        leaf_paths(sub_entry, &format!("{}/{}", prefix, sub_entry.name), paths);
+    }
+}
+
+/// Collects the entry's duplicated keys, then recurses into its sub-entries
+fn duplicate_keys(entry: &Entry, path: &str, found: &mut Vec<(String, String)>) {
+    let mut seen = HashSet::new();
+    let mut reported = HashSet::new();
+    for key in entry.expressions.keys() {
+        if !seen.insert(key) && reported.insert(key) {
+            found.push((path.to_owned(), key.clone()));
+        }
+    }
+
+    for sub_entry in &entry.entries {
+        duplicate_keys(sub_entry, &format!("{}/{}", path, sub_entry.name), found);
+    }
+}
+
+/// Checks whether an entry's `appid` expression holds the given app ID
+fn has_appid(entry: &Entry, appid: u64) -> bool {
+    let value = entry.expressions.get("appid");
+    value.and_then(|value| value.parse::<u64>().ok()) == Some(appid)
+}
+
+/// Compares an entry's name against the one being searched for
+fn name_matches(entry: &Entry, name: &str, ignore_case: bool) -> bool {
+    if ignore_case {
+        entry.name.eq_ignore_ascii_case(name)
+    } else {
+        entry.name == name
+    }
+}
+
+/// Collects the entries with the given name along with their ancestors' names, depth first
+fn entries_named<'a>(
+    entries: &'a [Entry],
+    name: &str,
+    ignore_case: bool,
+    ancestors: &mut Vec<String>,
+    found: &mut Vec<(Vec<String>, &'a Entry)>,
+) {
+    for entry in entries {
+        if name_matches(entry, name, ignore_case) {
+            found.push((ancestors.clone(), entry));
+        }
+
+        ancestors.push(entry.name.clone());
+        entries_named(&entry.entries, name, ignore_case, ancestors, found);
+        ancestors.pop();
+    }
+}
+
+/// Calls `f` with the entries with the given name along with their ancestors' names, depth first
+fn entries_named_mut(
+    entries: &mut [Entry],
+    name: &str,
+    ignore_case: bool,
+    ancestors: &mut Vec<String>,
+    f: &mut impl FnMut(&[String], &mut Entry),
+) {
+    for entry in entries {
+        if name_matches(entry, name, ignore_case) {
+            f(ancestors, entry);
+        }
+
+        ancestors.push(entry.name.clone());
+        entries_named_mut(&mut entry.entries, name, ignore_case, ancestors, f);
+        ancestors.pop();
+    }
+}
+
+/// Returns the first entry matching the predicate, depth first
+fn find_first<'a>(entries: &'a [Entry], f: &impl Fn(&Entry) -> bool) -> Option<&'a Entry> {
+    entries
+        .iter()
+        .find_map(|entry| if f(entry) { Some(entry) } else { find_first(&entry.entries, f) })
+}
+
+/// Collects the entries matching the predicate, depth first
+fn collect<'a>(
+    entries: &'a [Entry],
+    matches: &mut impl FnMut(&Entry) -> bool,
+    found: &mut Vec<&'a Entry>,
+) {
+    for entry in entries {
+        if matches(entry) {
+            found.push(entry);
+        }
+        collect(&entry.entries, matches, found);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::*;
+
+    #[test]
+    fn entries_with_key() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+
+        let names = acf
+            .entries_with_key("manifest")
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["732", "731", "735", "2347770", "2347771", "2347774", "2347779"]);
+
+        let names = acf
+            .entries_with_key("language")
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["UserConfig", "MountedConfig"]);
+
+        assert_eq!(acf.entries_with_key("appid")[0].name, "AppState");
+        assert!(acf.entries_with_key("missing").is_empty());
+    }
+
+    #[test]
+    fn find_first() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+
+        let found = acf.find_first(|entry| entry.expressions.len() > 2);
+        assert_eq!(found.unwrap().name, "AppState");
+
+        let found = acf.find_first(|entry| {
+            entry.expressions.len() > 2 && entry.name != "AppState"
+        });
+        assert_eq!(found.unwrap().name, "2347779");
+
+        let found = acf.find_first(|entry| entry.expressions.contains_key("language"));
+        assert_eq!(found.unwrap().name, "UserConfig");
+        assert!(acf.find_first(|entry| entry.expressions.len() > 100).is_none());
+    }
+
+    #[test]
+    fn find_by_appid() {
+        let mut acf = parse_acf("./acfs/appmanifest_745.acf").unwrap();
+        acf.append(parse_acf("./acfs/appmanifest_730.acf").unwrap());
+
+        let entry = acf.find_by_appid_mut(730).unwrap();
+        entry.expressions.insert("StateFlags", "1026");
+
+        assert_eq!(acf.entries[1].expressions["StateFlags"], "1026");
+        assert_ne!(acf.entries[0].expressions["StateFlags"], "1026");
+        assert_eq!(acf.find_by_appid(730).unwrap().expressions["buildid"], "20040392");
+        assert!(acf.find_by_appid(1).is_none());
+        assert!(acf.find_by_appid_mut(1).is_none());
+    }
+
+    #[test]
+    fn counts() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        assert_eq!(acf.entry_count(), 12);
+        assert_eq!(acf.expression_count(), 44);
+
+        acf.append(parse_acf("./acfs/simple.acf").unwrap());
+        assert_eq!(acf.entry_count(), 13);
+        assert_eq!(acf.expression_count(), 50);
+        assert_eq!(Acf::default().entry_count(), 0);
+    }
+
+    #[test]
+    fn sum_u64() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        acf.append(parse_acf("./acfs/appmanifest_745.acf").unwrap());
+        let sizes = acf.entries.iter().map(|entry| entry.parse_value::<u64>("SizeOnDisk"));
+        let expected = sizes.map(Result::unwrap).sum::<u64>();
+        assert_eq!(acf.sum_u64("SizeOnDisk"), expected);
+
+        let src = concat!(
+            "\"A\" { \"size\" \"8\" \"B\" { \"size\" \"16\" \"size\" \"1\" } }",
+            "\"C\" { \"size\" \"large\" \"D\" { \"size\" \"32\" } }",
+        );
+        let mut acf = parse_acf_str(src).unwrap();
+        assert_eq!(acf.sum_u64("size"), 56);
+        assert_eq!(acf.sum_u64("missing"), 0);
+
+        acf.append(parse_acf_str("\"E\" { \"size\" \"18446744073709551615\" }").unwrap());
+        assert_eq!(acf.sum_u64("size"), u64::MAX);
+    }
+
+    #[test]
+    fn find_entries_by_name() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        acf.append(parse_acf("./acfs/appmanifest_745.acf").unwrap());
+
+        let depots = acf.find_entries_by_name("InstalledDepots");
+        assert_eq!(depots.len(), 2);
+        assert_eq!(depots[0].child_names()[0], "732");
+        assert_eq!(acf.find_entries_by_name("731").len(), 1);
+        assert_eq!(acf.find_entries_by_name("AppState").len(), 2);
+        assert!(acf.find_entries_by_name("installeddepots").is_empty());
+    }
+
+    #[test]
+    fn entries_named() {
+        let acf = parse_acf("./acfs/config.vdf").unwrap();
+
+        let paths = acf
+            .entries_named("Software", false)
+            .into_iter()
+            .map(|(path, _)| path.join("/"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            [
+                "InstallConfigStore",
+                "InstallConfigStore/Software/Valve",
+                "InstallConfigStore/Music/Library"
+            ]
+        );
+        assert_eq!(acf.entries_named("Software", false)[1].1.expressions["a"], "1");
+
+        let found = acf.entries_named("SOFTWARE", true);
+        assert_eq!(found.len(), 4);
+        assert_eq!(found[3].0, ["InstallConfigStore", "Extra"]);
+        assert!(acf.entries_named("SOFTWARE", false).is_empty());
+    }
+
+    #[test]
+    fn entries_named_mut() {
+        let mut acf = parse_acf("./acfs/config.vdf").unwrap();
+        acf.entries_named_mut("software", true, |ancestors, entry| {
+            entry.expressions.insert("depth", ancestors.len().to_string());
+        });
+
+        let depths = acf
+            .entries_named("software", true)
+            .into_iter()
+            .map(|(_, entry)| entry.expressions["depth"].as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(depths, ["1", "3", "3", "2"]);
+    }
+
+    #[test]
+    fn entries_at_depth() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+
+        let names = acf
+            .entries_at_depth(1)
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["InstalledDepots", "SharedDepots", "UserConfig", "MountedConfig"]);
+
+        assert_eq!(acf.entries_at_depth(0).len(), 1);
+        assert_eq!(acf.entries_at_depth(2)[0].name, "732");
+        assert!(acf.entries_at_depth(10).is_empty());
+    }
+
+    #[test]
+    fn iter_leaves() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let leaves = acf.iter_leaves().collect::<Vec<_>>();
+
+        assert_eq!(leaves[0], ("AppState".to_owned(), "appid", "730"));
+        assert!(leaves.contains(&("AppState/InstalledDepots/731".to_owned(), "size", "8")));
+        assert_eq!(leaves.len(), acf.leaf_paths().len());
+
+        let paths = leaves
+            .iter()
+            .map(|(path, key, _)| format!("{}/{}", path, key))
+            .collect::<Vec<_>>();
+        assert_eq!(paths, acf.leaf_paths());
+    }
+
+    #[test]
+    fn leaf_paths() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let paths = acf.leaf_paths();
+
+        assert_eq!(paths[0], "AppState/appid");
+        assert!(paths.contains(&"AppState/InstalledDepots/731/size".to_owned()));
+        assert!(paths.contains(&"AppState/UserConfig/language".to_owned()));
+        assert!(!paths.contains(&"AppState/InstalledDepots".to_owned()));
+
+        let root_paths = paths.iter().filter(|path| path.matches('/').count() == 1);
+        assert_eq!(root_paths.count(), acf.entries[0].expressions.len());
+    }
+
+    #[test]
+    fn key_set() {
+        let cs2 = parse_acf("./acfs/appmanifest_730.acf").unwrap().key_set();
+        let redist = parse_acf("./acfs/appmanifest_228980.acf").unwrap().key_set();
+        let changed = cs2
+            .symmetric_difference(&redist)
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+
+        assert!(cs2.contains("AppState/appid"));
+        assert!(!changed.contains(&"AppState/appid"));
+        assert!(changed.contains(&"AppState/SharedDepots/228988"));
+        assert!(changed.contains(&"AppState/InstallScripts/229033"));
+
+        let user_config = changed
+            .into_iter()
+            .filter(|path| path.starts_with("AppState/UserConfig/"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            user_config,
+            [
+                "AppState/UserConfig/BetaKey",
+                "AppState/UserConfig/DisabledDLC",
+                "AppState/UserConfig/language",
+                "AppState/UserConfig/optionaldlc",
+            ]
+        );
+    }
+
+    #[test]
+    fn duplicate_keys() {
+        let acf = parse_acf("./acfs/duplicate_keys.acf").unwrap();
+
+        assert_eq!(
+            acf.duplicate_keys(),
+            [
+                ("AppState".to_owned(), "StateFlags".to_owned()),
+                ("AppState/InstalledDepots/731".to_owned(), "size".to_owned()),
+            ]
+        );
+
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        assert!(acf.duplicate_keys().is_empty());
+    }
+}
@@ -0,0 +1,447 @@
+use crate::errors::*;
+use crate::parser::{Acf, Entry};
+use serde::ser::{self, Impossible, Serialize};
+use std::fmt::Display;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// Serializes a user type into a document with a single root entry of the given name
+///
+/// Behaves like [`to_entry`]. Write the result out with [`Acf::to_vdf_string`]
+pub fn to_acf<T: Serialize + ?Sized>(name: &str, value: &T) -> Result<Acf> {
+    Ok(Acf {
+        entries: vec![to_entry(name, value)?],
+    })
+}
+
+/// Serializes a user type into an entry with the given name
+///
+/// The value must be a struct or map. Its fields become expressions when they hold strings,
+/// numbers, booleans (written as `1`/`0`), or unit enum variants (written by name), and
+/// sub-entries when they hold structs or maps. Sequences become sub-entries whose members are
+/// named by position (`"0"`, `"1"`, ...). `None` and unit fields are left out. Map keys must be
+/// strings or numbers. Anything else, such as a top-level sequence or an enum variant with data,
+/// is reported as [`AcfError::Serde`]
+///
+/// The output reads back with [`from_entry`](crate::de::from_entry), apart from sequences of
+/// values, which read back as maps
+pub fn to_entry<T: Serialize + ?Sized>(name: &str, value: &T) -> Result<Entry> {
+    match value.serialize(MemberSerializer)? {
+        Member::Entry(mut entry) => {
+            entry.name = name.to_owned();
+            Ok(entry)
+        }
+        _ => Err(unsupported("expected a struct or map at the top level")),
+    }
+}
+
+impl ser::Error for AcfError {
+    fn custom<T: Display>(msg: T) -> Self {
+        AcfError::Serde(msg.to_string())
+    }
+}
+
+/// Result of serializing a single member of an entry
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+enum Member {
+    /// An expression's value
+    Value(String),
+
+    /// A sub-entry, named once it's added to its parent
+    Entry(Entry),
+
+    /// A sub-entry built from a sequence
+    Seq(Entry),
+
+    /// Nothing to write, as for `None`
+    Skip,
+}
+
+/// Adds a member to an entry under the given name
+fn push_member(entry: &mut Entry, name: String, member: Member) {
+    match member {
+        Member::Value(value) => {
+            entry.expressions.insert(name, value);
+        }
+        Member::Entry(mut sub_entry) | Member::Seq(mut sub_entry) => {
+            sub_entry.name = name;
+            entry.entries.push(sub_entry);
+        }
+        Member::Skip => {}
+    }
+}
+
+/// Creates an error for a shape that has no ACF representation
+fn unsupported(message: &str) -> AcfError {
+    AcfError::Serde(message.to_owned())
+}
+
+/// Creates an error for an enum variant holding data, which has no ACF representation
+fn variant_with_data() -> AcfError {
+    unsupported("enum variants with data can't be written as ACF")
+}
+
+/// Serializer producing a single member of an entry
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct MemberSerializer;
+
+/// Implements serialization of types written out with their `Display` implementation
+macro_rules! serialize_display {
+    ($($method:ident: $ty:ty,)*) => {
+        $(
+            fn $method(self, v: $ty) -> Result<Member> {
+                Ok(Member::Value(v.to_string()))
+            }
+        )*
+    };
+}
+
+impl ser::Serializer for MemberSerializer {
+    type Ok = Member;
+    type Error = AcfError;
+    type SerializeSeq = SeqBuilder;
+    type SerializeTuple = SeqBuilder;
+    type SerializeTupleStruct = SeqBuilder;
+    type SerializeTupleVariant = Impossible<Member, AcfError>;
+    type SerializeMap = MapBuilder;
+    type SerializeStruct = MapBuilder;
+    type SerializeStructVariant = Impossible<Member, AcfError>;
+
+    fn serialize_bool(self, v: bool) -> Result<Member> {
+        Ok(Member::Value(if v { "1" } else { "0" }.to_owned()))
+    }
+
+    serialize_display! {
+        serialize_i8: i8,
+        serialize_i16: i16,
+        serialize_i32: i32,
+        serialize_i64: i64,
+        serialize_i128: i128,
+        serialize_u8: u8,
+        serialize_u16: u16,
+        serialize_u32: u32,
+        serialize_u64: u64,
+        serialize_u128: u128,
+        serialize_f32: f32,
+        serialize_f64: f64,
+        serialize_char: char,
+        serialize_str: &str,
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Member> {
+        Err(unsupported("byte arrays can't be written as ACF"))
+    }
+
+    fn serialize_none(self) -> Result<Member> {
+        Ok(Member::Skip)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Member> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Member> {
+        Ok(Member::Skip)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Member> {
+        Ok(Member::Skip)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Member> {
+        Ok(Member::Value(variant.to_owned()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Member> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Member> {
+        Err(variant_with_data())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqBuilder> {
+        Ok(SeqBuilder::default())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<SeqBuilder> {
+        Ok(SeqBuilder::default())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<SeqBuilder> {
+        Ok(SeqBuilder::default())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(variant_with_data())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapBuilder> {
+        Ok(MapBuilder::default())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<MapBuilder> {
+        Ok(MapBuilder::default())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(variant_with_data())
+    }
+}
+
+/// Builds an entry from a sequence, naming members by position
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+#[derive(Default)]
+struct SeqBuilder {
+    /// Entry being built
+    entry: Entry,
+
+    /// Position of the next element
+    index: usize,
+}
+
+impl SeqBuilder {
+    /// Adds the next element
+    fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let member = value.serialize(MemberSerializer)?;
+        push_member(&mut self.entry, self.index.to_string(), member);
+        self.index += 1;
+
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SeqBuilder {
+    type Ok = Member;
+    type Error = AcfError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Member> {
+        Ok(Member::Seq(self.entry))
+    }
+}
+
+impl ser::SerializeTuple for SeqBuilder {
+    type Ok = Member;
+    type Error = AcfError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Member> {
+        Ok(Member::Seq(self.entry))
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqBuilder {
+    type Ok = Member;
+    type Error = AcfError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.push(value)
+    }
+
+    fn end(self) -> Result<Member> {
+        Ok(Member::Seq(self.entry))
+    }
+}
+
+/// Builds an entry from a struct or map
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+#[derive(Default)]
+struct MapBuilder {
+    /// Entry being built
+    entry: Entry,
+
+    /// Key whose value is expected next
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapBuilder {
+    type Ok = Member;
+    type Error = AcfError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        match key.serialize(MemberSerializer)? {
+            Member::Value(key) => {
+                self.key = Some(key);
+                Ok(())
+            }
+            _ => Err(unsupported("map keys must be strings or numbers")),
+        }
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        let Some(key) = self.key.take() else {
+            return Err(unsupported("map value serialized before its key"));
+        };
+        let member = value.serialize(MemberSerializer)?;
+        push_member(&mut self.entry, key, member);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Member> {
+        Ok(Member::Entry(self.entry))
+    }
+}
+
+impl ser::SerializeStruct for MapBuilder {
+    type Ok = Member;
+    type Error = AcfError;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        let member = value.serialize(MemberSerializer)?;
+        push_member(&mut self.entry, key.to_owned(), member);
+
+        Ok(())
+    }
+
+    fn end(self) -> Result<Member> {
+        Ok(Member::Entry(self.entry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::de::from_entry;
+    use crate::parser::*;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct App {
+        appid: u32,
+        name: String,
+        #[serde(rename = "StateFlags")]
+        state_flags: u8,
+        #[serde(rename = "AutoUpdate")]
+        auto_update: bool,
+        #[serde(rename = "BetaKey")]
+        beta_key: Option<String>,
+        #[serde(rename = "InstalledDepots")]
+        installed_depots: BTreeMap<u32, Depot>,
+        #[serde(rename = "UserConfig")]
+        user_config: Config,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Depot {
+        manifest: u64,
+        size: u64,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Config {
+        language: Language,
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Language {
+        English,
+        German,
+    }
+
+    fn app() -> App {
+        let depot = |manifest, size| Depot { manifest, size };
+        App {
+            appid: 730,
+            name: "Counter-Strike 2".into(),
+            state_flags: 4,
+            auto_update: true,
+            beta_key: None,
+            installed_depots: BTreeMap::from([
+                (731, depot(8593492943720630015, 8)),
+                (2347770, depot(5422453633692855293, 48989642342)),
+            ]),
+            user_config: Config {
+                language: Language::German,
+            },
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let acf = to_acf("AppState", &app()).unwrap();
+        let serialized = acf.to_vdf_string();
+        assert!(serialized.starts_with("\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n"));
+        assert!(serialized.contains("\t\"AutoUpdate\"\t\t\"1\"\n"));
+        let depot = "\t\t\"731\"\n\t\t{\n\t\t\t\"manifest\"\t\t\"8593492943720630015\"\n";
+        assert!(serialized.contains(depot));
+        assert!(serialized.contains("\t\t\"language\"\t\t\"german\"\n"));
+        assert!(!serialized.contains("BetaKey"));
+
+        let parsed = parse_acf_str(&serialized).unwrap();
+        assert_eq!(from_entry::<App>(&parsed.entries[0]).unwrap(), app());
+    }
+
+    #[test]
+    fn sequences() {
+        let tags = BTreeMap::from([("tags", vec!["favorite", "launchers"])]);
+        let entry = to_entry("0", &tags).unwrap();
+        assert_eq!(entry.entries[0].name, "tags");
+        assert_eq!(entry.entries[0].expressions["1"], "launchers");
+
+        let depots = BTreeMap::from([(
+            "depots",
+            vec![Depot {
+                manifest: 1,
+                size: 2,
+            }],
+        )]);
+        let entry = to_entry("Root", &depots).unwrap();
+        assert_eq!(entry.entries[0].entries[0].name, "0");
+        assert_eq!(entry.entries[0].entries[0].expressions["size"], "2");
+    }
+
+    #[test]
+    fn unsupported_shapes() {
+        assert!(to_acf("Root", &vec![1, 2]).unwrap_err().is_serde());
+        assert!(to_acf("Root", "value").unwrap_err().is_serde());
+
+        let keyed = BTreeMap::from([((1, 2), "value")]);
+        assert!(to_entry("Root", &keyed).unwrap_err().is_serde());
+    }
+}
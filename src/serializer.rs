@@ -0,0 +1,679 @@
+use crate::errors::UnquotedError;
+use crate::parser::{Acf, Entry};
+#[cfg(feature = "tracing")]
+use crate::trace;
+use std::io::{self, Write};
+
+/// Width of a tab stop when aligning values into a column
+const TAB_WIDTH: usize = 8;
+
+/// Layout choices that differ between the serializer variants
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+struct Style {
+    /// Aligns the values of each entry into a column
+    align: bool,
+
+    /// Escapes quotes, backslashes, and line breaks as well as tabs
+    escape_all: bool,
+
+    /// Quotes entry names and expression keys
+    quote_keys: bool,
+
+    /// Quotes expression values
+    quote_values: bool,
+}
+
+/// Layout of [`Acf::to_vdf_string`]
+const STEAM: Style = Style {
+    align: false,
+    escape_all: false,
+    quote_keys: true,
+    quote_values: true,
+};
+
+/// Layout of [`Acf::to_vdf_string_aligned`]
+const ALIGNED: Style = Style {
+    align: true,
+    escape_all: false,
+    quote_keys: true,
+    quote_values: true,
+};
+
+/// Layout of [`Acf::to_appinfo_text`]
+const APPINFO: Style = Style {
+    align: false,
+    escape_all: true,
+    quote_keys: true,
+    quote_values: true,
+};
+
+/// Quoting choices for [`Acf::to_vdf_string_pretty`]
+///
+/// Steam quotes every string, which is the default. Strings written without quotes must be
+/// non-empty and can't contain whitespace, quotes, or braces, or begin with `//`, as they would
+/// otherwise run into the surrounding layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PrettyConfig {
+    /// Quotes entry names and expression keys
+    pub quote_keys: bool,
+
+    /// Quotes expression values
+    pub quote_values: bool,
+}
+
+impl Default for PrettyConfig {
+    fn default() -> Self {
+        Self {
+            quote_keys: true,
+            quote_values: true,
+        }
+    }
+}
+
+impl PrettyConfig {
+    /// Returns the layout of [`Acf::to_vdf_string`] with this config's quoting
+    fn style(&self) -> Style {
+        Style {
+            quote_keys: self.quote_keys,
+            quote_values: self.quote_values,
+            ..STEAM
+        }
+    }
+}
+
+impl Acf {
+    /// Serializes the document back into the ACF format
+    ///
+    /// Output follows the layout Steam writes: names and values are quoted, braces sit on their
+    /// own lines, nesting is indented with tabs, and keys are separated from values by two tabs.
+    /// Tabs within names and values are written as `\t` so they can't be mistaken for layout.
+    /// Everything else is written as stored, escape sequences included, apart from a quote or a
+    /// trailing backslash that would end the string early and a stored `\t` that would read back
+    /// as a tab. Those are escaped, so the output always parses
+    pub fn to_vdf_string(&self) -> String {
+        #[cfg(feature = "tracing")]
+        let span = trace::serialize_span();
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        let mut out = String::new();
+        self.entries
+            .iter()
+            .for_each(|entry| write_entry(&mut out, entry, 0, &STEAM));
+
+        #[cfg(feature = "tracing")]
+        trace::serialized(&span, &self.entries, &out);
+
+        out
+    }
+
+    /// Serializes the document like [`Acf::to_vdf_string`], but with the values of each entry
+    /// aligned into a column
+    ///
+    /// Within an entry, every key is followed by enough tabs to reach the same tab stop, assuming
+    /// tabs are eight columns wide. The column is chosen so the longest key is followed by two
+    /// tabs, so entries whose quoted keys are all shorter than a tab stop look as they do with
+    /// [`Acf::to_vdf_string`]. Each entry is aligned independently of its sub-entries
+    pub fn to_vdf_string_aligned(&self) -> String {
+        let mut out = String::new();
+        self.entries
+            .iter()
+            .for_each(|entry| write_entry(&mut out, entry, 0, &ALIGNED));
+
+        out
+    }
+
+    /// Serializes the document in the text form Steam uses for the sections of `appinfo.vdf`,
+    /// e.g. as printed by `steamcmd +app_info_print`
+    ///
+    /// The layout matches [`Acf::to_vdf_string`]. The difference is in how strings are written:
+    /// `appinfo.vdf` is binary, so its strings (as decoded by [`crate::binary`]) hold raw
+    /// characters rather than the escape sequences found in text files. Here backslashes, quotes,
+    /// line feeds, and carriage returns are escaped as `\\`, `\"`, `\n`, and `\r` along with
+    /// tabs, whereas [`Acf::to_vdf_string`] writes strings as stored. Reading the output back
+    /// with this crate's parser keeps those escapes in the stored values;
+    /// [`Entry::get_cow`] expands them again
+    pub fn to_appinfo_text(&self) -> String {
+        let mut out = String::new();
+        self.entries
+            .iter()
+            .for_each(|entry| write_entry(&mut out, entry, 0, &APPINFO));
+
+        out
+    }
+
+    /// Serializes the document like [`Acf::to_vdf_string`], but with quoting chosen by `config`
+    ///
+    /// Entry names count as keys. Fails without producing any output if a string that should be
+    /// unquoted can't be written that way, as described for [`PrettyConfig`]
+    pub fn to_vdf_string_pretty(&self, config: &PrettyConfig) -> Result<String, UnquotedError> {
+        self.entries
+            .iter()
+            .try_for_each(|entry| check_unquoted(entry, &entry.name, config))?;
+
+        let style = config.style();
+        let mut out = String::new();
+        self.entries
+            .iter()
+            .for_each(|entry| write_entry(&mut out, entry, 0, &style));
+
+        Ok(out)
+    }
+
+    /// Writes the document to `w` as [`Acf::to_vdf_string_pretty`] would format it, without
+    /// building the whole output in memory
+    ///
+    /// The output is passed to `w` an entry at a time, so wrapping files in an
+    /// [`io::BufWriter`] is recommended. Strings that can't be written unquoted are reported as
+    /// [`io::ErrorKind::InvalidInput`] wrapping an [`UnquotedError`], before anything is written
+    pub fn write_to_pretty<W: Write>(&self, w: &mut W, config: &PrettyConfig) -> io::Result<()> {
+        self.entries
+            .iter()
+            .try_for_each(|entry| check_unquoted(entry, &entry.name, config))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let style = config.style();
+        let mut buf = String::new();
+        self.entries
+            .iter()
+            .try_for_each(|entry| stream_entry(w, &mut buf, entry, 0, &style))
+    }
+
+    /// Estimates the length in bytes of [`Acf::to_vdf_string`]'s output, e.g. to pre-size a buffer
+    ///
+    /// The layout is accounted for exactly, so the estimate is only short by one byte for every
+    /// tab within a name or value, or other character that has to be escaped
+    pub fn serialized_len_estimate(&self) -> usize {
+        self.entries.iter().map(|entry| entry_len(entry, 0)).sum()
+    }
+}
+
+impl Entry {
+    /// Serializes the entry (and its sub-entries) into the ACF format
+    pub fn to_vdf_string(&self) -> String {
+        #[cfg(feature = "tracing")]
+        let span = trace::serialize_span();
+        #[cfg(feature = "tracing")]
+        let _entered = span.enter();
+
+        let mut out = String::new();
+        write_entry(&mut out, self, 0, &STEAM);
+
+        #[cfg(feature = "tracing")]
+        trace::serialized(&span, std::slice::from_ref(self), &out);
+
+        out
+    }
+
+    /// Serializes the entry (and its sub-entries) with values aligned into a column, as described
+    /// for [`Acf::to_vdf_string_aligned`]
+    pub fn to_vdf_string_aligned(&self) -> String {
+        let mut out = String::new();
+        write_entry(&mut out, self, 0, &ALIGNED);
+
+        out
+    }
+}
+
+/// Writes an entry at the given nesting depth in the given style
+fn write_entry(out: &mut String, entry: &Entry, depth: usize, style: &Style) {
+    write_head(out, entry, depth, style);
+
+    entry
+        .entries
+        .iter()
+        .for_each(|sub_entry| write_entry(out, sub_entry, depth + 1, style));
+
+    write_tail(out, depth);
+}
+
+/// Writes an entry like [`write_entry`], passing the output to `w` in pieces so that only one
+/// entry's expressions are held in `buf` at a time
+fn stream_entry<W: Write>(
+    w: &mut W,
+    buf: &mut String,
+    entry: &Entry,
+    depth: usize,
+    style: &Style,
+) -> io::Result<()> {
+    buf.clear();
+    write_head(buf, entry, depth, style);
+    w.write_all(buf.as_bytes())?;
+
+    for sub_entry in &entry.entries {
+        stream_entry(w, buf, sub_entry, depth + 1, style)?;
+    }
+
+    buf.clear();
+    write_tail(buf, depth);
+    w.write_all(buf.as_bytes())
+}
+
+/// Writes an entry's name, opening brace, and expressions
+fn write_head(out: &mut String, entry: &Entry, depth: usize, style: &Style) {
+    let quote = if style.escape_all {
+        write_escaped
+    } else {
+        write_quoted
+    };
+    let write_key = if style.quote_keys { quote } else { write_bare };
+    let write_value = if style.quote_values {
+        quote
+    } else {
+        write_bare
+    };
+
+    write_indent(out, depth);
+    write_key(out, &entry.name);
+    out.push('\n');
+
+    write_indent(out, depth);
+    out.push_str("{\n");
+
+    // Tab stop the values start at, counted from the start of the keys
+    let longest = entry.expressions.keys().map(|key| quoted_width(key)).max();
+    let column = longest.map_or(0, |width| width / TAB_WIDTH + 2);
+
+    for (key, value) in &entry.expressions {
+        write_indent(out, depth + 1);
+        write_key(out, key);
+        let tabs = if style.align {
+            column - quoted_width(key) / TAB_WIDTH
+        } else {
+            2
+        };
+        (0..tabs).for_each(|_| out.push('\t'));
+        write_value(out, value);
+        out.push('\n');
+    }
+}
+
+/// Writes an entry's closing brace
+fn write_tail(out: &mut String, depth: usize) {
+    write_indent(out, depth);
+    out.push_str("}\n");
+}
+
+/// Checks that every string `config` leaves unquoted can be written that way, recursing into
+/// sub-entries
+fn check_unquoted(entry: &Entry, path: &str, config: &PrettyConfig) -> Result<(), UnquotedError> {
+    let unquoted = |path: &str, token: &str| {
+        if is_bare_token(token) {
+            Ok(())
+        } else {
+            Err(UnquotedError {
+                path: path.to_owned(),
+                token: token.to_owned(),
+            })
+        }
+    };
+
+    if !config.quote_keys {
+        unquoted(path, &entry.name)?;
+    }
+    for (key, value) in &entry.expressions {
+        let key_path = format!("{}/{}", path, key);
+        if !config.quote_keys {
+            unquoted(&key_path, key)?;
+        }
+        if !config.quote_values {
+            unquoted(&key_path, value)?;
+        }
+    }
+
+    entry.entries.iter().try_for_each(|sub_entry| {
+        check_unquoted(sub_entry, &format!("{}/{}", path, sub_entry.name), config)
+    })
+}
+
+/// Checks whether a string can be written without quotes
+fn is_bare_token(val: &str) -> bool {
+    let reserved = |c: char| c.is_whitespace() || matches!(c, '"' | '{' | '}');
+    !val.is_empty() && !val.starts_with("//") && !val.contains(reserved)
+}
+
+/// Computes the serialized length of an entry at the given nesting depth, ignoring escapes
+fn entry_len(entry: &Entry, depth: usize) -> usize {
+    // Name line, then the opening and closing brace lines
+    let lines = 3 * depth + 7;
+    let expressions = entry
+        .expressions
+        .iter()
+        .map(|(key, value)| depth + 1 + key.len() + value.len() + 7)
+        .sum::<usize>();
+    let entries = entry
+        .entries
+        .iter()
+        .map(|sub_entry| entry_len(sub_entry, depth + 1))
+        .sum::<usize>();
+
+    entry.name.len() + lines + expressions + entries
+}
+
+/// Returns the number of columns a string literal takes up once quoted and escaped
+fn quoted_width(val: &str) -> usize {
+    literal_pieces(val)
+        .map(|piece| piece.chars().count())
+        .sum::<usize>()
+        + 2
+}
+
+/// Writes a string literal, escaping tabs
+pub(crate) fn write_quoted(out: &mut String, val: &str) {
+    out.push('"');
+    literal_pieces(val).for_each(|piece| out.push_str(piece));
+    out.push('"');
+}
+
+/// Splits a stored string into the pieces written for it within a string literal
+///
+/// Escape sequences are kept as written, apart from `\t`, which would read back as a tab. Tabs,
+/// quotes, and a trailing backslash are escaped so they can't end the string or be mistaken for
+/// layout
+fn literal_pieces(val: &str) -> impl Iterator<Item = &str> {
+    let mut rest = val;
+    std::iter::from_fn(move || {
+        let mut chars = rest.chars();
+        let (piece, len) = match chars.next()? {
+            '\\' => match chars.next() {
+                Some('t') => ("\\\\", 1),
+                Some(next) => (&rest[..1 + next.len_utf8()], 1 + next.len_utf8()),
+                None => ("\\\\", 1),
+            },
+            '"' => ("\\\"", 1),
+            '\t' => ("\\t", 1),
+            c => (&rest[..c.len_utf8()], c.len_utf8()),
+        };
+        rest = &rest[len..];
+
+        Some(piece)
+    })
+}
+
+/// Writes a string as is, without quotes
+fn write_bare(out: &mut String, val: &str) {
+    out.push_str(val);
+}
+
+/// Writes a string literal, escaping backslashes, quotes, tabs, and line breaks
+fn write_escaped(out: &mut String, val: &str) {
+    out.push('"');
+    for c in val.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '"' => out.push_str("\\\""),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            _ => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// Writes indentation for the given nesting depth
+fn write_indent(out: &mut String, depth: usize) {
+    (0..depth).for_each(|_| out.push('\t'));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::*;
+
+    #[test]
+    fn round_trip() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let serialized = acf.to_vdf_string();
+        assert_eq!(parse_acf_str(&serialized).unwrap(), acf);
+    }
+
+    #[test]
+    fn matches_steam_layout() {
+        let src = std::fs::read_to_string("./acfs/appmanifest_730.acf").unwrap();
+        let acf = parse_acf_str(&src).unwrap();
+        assert_eq!(acf.to_vdf_string(), src);
+    }
+
+    #[test]
+    fn braces_inside_values() {
+        let src = "\"Root\"{\"pattern\"\"{foo}\"\"close\"\"}\"\"Child\"{\"open\"\"{\"}}";
+        let acf = parse_acf_str(src).unwrap();
+        let root_entry = &acf.entries[0];
+        assert_eq!(root_entry.expressions["pattern"], "{foo}");
+        assert_eq!(root_entry.expressions["close"], "}");
+        assert_eq!(root_entry.entries[0].expressions["open"], "{");
+
+        let serialized = acf.to_vdf_string();
+        assert!(serialized.contains("\t\"pattern\"\t\t\"{foo}\"\n"));
+        assert_eq!(parse_acf_str(&serialized).unwrap(), acf);
+    }
+
+    #[test]
+    fn serialized_len_estimate() {
+        for path in [
+            "./acfs/appmanifest_730.acf",
+            "./acfs/appmanifest_228980.acf",
+            "./acfs/simple.acf",
+        ] {
+            let acf = parse_acf(path).unwrap();
+            assert_eq!(acf.serialized_len_estimate(), acf.to_vdf_string().len());
+        }
+
+        let acf = parse_acf_str("\"Root\" { \"text\" \"a\tb\tc\" \"Child\" { } }").unwrap();
+        let estimate = acf.serialized_len_estimate();
+        let actual = acf.to_vdf_string().len();
+        assert!(estimate <= actual && actual <= estimate * 2);
+        assert_eq!(actual - estimate, 2);
+    }
+
+    #[test]
+    fn tabs_inside_values() {
+        let mut acf = parse_acf_str("\"Root\" { \"path\" \"C:\\\\tools\" }").unwrap();
+        acf.entries[0].expressions.insert("text", "a\tb");
+
+        let serialized = acf.to_vdf_string();
+        assert!(serialized.contains("\t\"text\"\t\t\"a\\tb\"\n"));
+        assert!(serialized.contains("\t\"path\"\t\t\"C:\\\\tools\"\n"));
+
+        let parsed = parse_acf_str(&serialized).unwrap();
+        assert_eq!(parsed.entries[0].expressions["text"], "a\tb");
+        assert_eq!(parsed, acf);
+    }
+
+    #[test]
+    fn aligned_values() {
+        let src = concat!(
+            "\"Root\" { \"a\" \"1\" \"longer_key_name\" \"2\" \"mid_key\" \"3\" ",
+            "\"Child\" { \"b\" \"4\" } }",
+        );
+        let acf = parse_acf_str(src).unwrap();
+
+        let aligned = acf.to_vdf_string_aligned();
+        assert_eq!(
+            aligned,
+            concat!(
+                "\"Root\"\n{\n",
+                "\t\"a\"\t\t\t\t\"1\"\n",
+                "\t\"longer_key_name\"\t\t\"2\"\n",
+                "\t\"mid_key\"\t\t\t\"3\"\n",
+                "\t\"Child\"\n\t{\n",
+                "\t\t\"b\"\t\t\"4\"\n",
+                "\t}\n}\n",
+            )
+        );
+        assert_eq!(parse_acf_str(&aligned).unwrap(), acf);
+
+        let acf = parse_acf_str("\"Root\" { \"appid\" \"730\" \"name\" \"CS2\" }").unwrap();
+        assert_eq!(acf.to_vdf_string_aligned(), acf.to_vdf_string());
+    }
+
+    #[test]
+    fn appinfo_text() {
+        let mut common = Entry {
+            name: "common".into(),
+            ..Default::default()
+        };
+        common.expressions.insert("name", "Counter-Strike 2");
+        common.expressions.insert("type", "Game");
+        common
+            .expressions
+            .insert("description", "Say \"gg\"\nthen leave");
+
+        let mut launch = Entry {
+            name: "0".into(),
+            ..Default::default()
+        };
+        launch
+            .expressions
+            .insert("executable", "game\\bin\\win64\\cs2.exe");
+        launch.expressions.insert("arguments", "-steam\t-insecure");
+        let mut config = Entry {
+            name: "config".into(),
+            ..Default::default()
+        };
+        config
+            .expressions
+            .insert("installdir", "Counter-Strike Global Offensive");
+        config.entries.push(Entry {
+            name: "launch".into(),
+            entries: vec![launch],
+            ..Default::default()
+        });
+
+        let acf = Acf {
+            entries: vec![Entry {
+                name: "730".into(),
+                entries: vec![common, config],
+                ..Default::default()
+            }],
+        };
+        let expected = std::fs::read_to_string("./acfs/appinfo_730.txt").unwrap();
+        assert_eq!(acf.to_appinfo_text(), expected);
+
+        let reparsed = parse_acf_str(&expected).unwrap();
+        let common = &reparsed.entries[0].entries[0];
+        let launch = &reparsed.entries[0].entries[1].entries[0].entries[0];
+        let description = common.get_cow("description").unwrap();
+        assert_eq!(description, "Say \"gg\"\nthen leave");
+        let executable = launch.get_cow("executable").unwrap();
+        assert_eq!(executable, "game\\bin\\win64\\cs2.exe");
+        assert_eq!(launch.get_cow("arguments").unwrap(), "-steam\t-insecure");
+
+        let mut line_endings = Entry {
+            name: "notes".into(),
+            ..Default::default()
+        };
+        line_endings.expressions.insert("text", "a\rb\r\n");
+        let acf = Acf {
+            entries: vec![line_endings],
+        };
+        let reparsed = parse_acf_str(&acf.to_appinfo_text()).unwrap();
+        assert_eq!(reparsed.entries[0].get_cow("text").unwrap(), "a\rb\r\n");
+
+        let plain = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let text = plain.to_appinfo_text();
+        assert_eq!(text, plain.to_vdf_string().replace('\\', "\\\\"));
+    }
+
+    #[test]
+    fn unquoted_keys() {
+        let acf = parse_acf("./acfs/simple.acf").unwrap();
+        let config = PrettyConfig {
+            quote_keys: false,
+            ..Default::default()
+        };
+
+        let out = acf.to_vdf_string_pretty(&config).unwrap();
+        assert!(out.starts_with("AppState\n{\n\tappid\t\t\"730\"\n\tuniverse\t\t\"1\"\n"));
+        assert!(out.contains("\tname\t\t\"Counter-Strike 2\"\n"));
+
+        let default = acf.to_vdf_string_pretty(&PrettyConfig::default()).unwrap();
+        assert_eq!(default, acf.to_vdf_string());
+
+        let config = PrettyConfig {
+            quote_values: false,
+            ..Default::default()
+        };
+        let err = acf.to_vdf_string_pretty(&config).unwrap_err();
+        assert_eq!(err.path, "AppState/LauncherPath");
+        assert_eq!(err.token, r"C:\\Program Files (x86)\\Steam\\steam.exe");
+    }
+
+    #[test]
+    fn write_to_pretty() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let mut out = Vec::new();
+        acf.write_to_pretty(&mut out, &PrettyConfig::default())
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), acf.to_vdf_string());
+
+        let config = PrettyConfig {
+            quote_keys: false,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        acf.write_to_pretty(&mut out, &config).unwrap();
+        let expected = acf.to_vdf_string_pretty(&config).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+
+        let config = PrettyConfig {
+            quote_values: false,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let err = acf.write_to_pretty(&mut out, &config).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn round_trip_escapes() {
+        let strings = [
+            "",
+            "plain",
+            r"C:\Program Files\Steam",
+            r"trailing\",
+            r"\t is not a tab",
+            r"\n is not a line feed",
+            r#"say "hi""#,
+            r#"\""#,
+            "tab\there",
+            "line\nfeed\r\n",
+            "{ // not a comment }",
+            "日本 🎮",
+        ];
+
+        for val in strings {
+            let mut entry = Entry {
+                name: val.to_owned(),
+                ..Default::default()
+            };
+            entry.expressions.insert(val, val);
+            entry.expressions.insert("value", val);
+            let acf = Acf {
+                entries: vec![entry],
+            };
+
+            let serialized = acf.to_vdf_string();
+            let parsed = parse_acf_str(&serialized).unwrap();
+            assert_eq!(parsed.to_vdf_string(), serialized);
+            assert_eq!(
+                parsed.entries[0].get_cow("value"),
+                acf.entries[0].get_cow("value"),
+                "{:?}",
+                serialized
+            );
+        }
+    }
+
+    #[cfg(feature = "test-strategies")]
+    proptest::proptest! {
+        #[test]
+        fn round_trip_generated(acf in crate::strategies::acf(&Default::default())) {
+            let serialized = acf.to_vdf_string();
+            proptest::prop_assert_eq!(parse_acf_str(&serialized).unwrap(), acf);
+        }
+    }
+}
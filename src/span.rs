@@ -0,0 +1,85 @@
+use crate::line_index::LineIndex;
+use std::fmt;
+use std::ops::Range;
+
+/// A byte range of source text
+///
+/// Offsets are byte indices into the parsed input, with `end` exclusive. Use a [`LineIndex`] to
+/// convert them into lines and columns
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    /// Byte offset of the first character
+    pub start: usize,
+
+    /// Byte offset one past the last character
+    pub end: usize,
+}
+
+impl Span {
+    /// Creates a span covering `start..end`
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the length of the span in bytes
+    pub fn len(&self) -> usize {
+        self.end.saturating_sub(self.start)
+    }
+
+    /// Returns `true` if the span covers no bytes
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the span as a range, suitable for slicing the source
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the 1-based `(line, column)` of the start of the span
+    pub fn start_line_col(&self, index: &LineIndex) -> (usize, usize) {
+        index.line_col(self.start)
+    }
+
+    /// Returns the 1-based `(line, column)` of the end of the span
+    pub fn end_line_col(&self, index: &LineIndex) -> (usize, usize) {
+        index.line_col(self.end)
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Self::new(range.start, range.end)
+    }
+}
+
+impl From<Span> for Range<usize> {
+    fn from(span: Span) -> Self {
+        span.range()
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_col() {
+        let src = "\"AppState\"\n{\n\t\"appid\"\t\t\"730\"\n}\n";
+        let index = LineIndex::new(src);
+        let start = src.find("\"appid\"").unwrap();
+        let span = Span::from(start..start + 7);
+
+        assert_eq!(span.len(), 7);
+        assert_eq!(&src[span.range()], "\"appid\"");
+        assert_eq!(span.start_line_col(&index), (3, 2));
+        assert_eq!(span.end_line_col(&index), (3, 9));
+        assert_eq!(span.to_string(), format!("{}..{}", start, start + 7));
+    }
+}
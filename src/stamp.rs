@@ -0,0 +1,100 @@
+use crate::errors::*;
+use std::fs;
+use std::io::Read;
+use std::time::SystemTime;
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+/// State of a file used to detect changes
+///
+/// > NOTE: This is an internal representation that is not shown to the user
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Stamp {
+    /// Modification time
+    pub(crate) modified: SystemTime,
+
+    /// Length in bytes
+    pub(crate) len: u64,
+
+    /// FNV-1a hash of the contents, or zero if they haven't been read
+    pub(crate) hash: u64,
+}
+
+impl Stamp {
+    /// Reads the modification time and length of the file at `path` without reading its contents
+    pub(crate) fn of(path: &str) -> Result<Self> {
+        let meta = fs::metadata(path).map_err(|e| AcfError::reading(path, &e))?;
+        let modified = meta.modified().map_err(|e| AcfError::reading(path, &e))?;
+
+        Ok(Self {
+            modified,
+            len: meta.len(),
+            hash: 0,
+        })
+    }
+
+    /// Returns `true` if the modification time and length match
+    pub(crate) fn matches_metadata(&self, other: &Stamp) -> bool {
+        self.modified == other.modified && self.len == other.len
+    }
+}
+
+/// Reads the file at `path` along with its current state
+///
+/// The metadata is taken before reading, so a write racing with the read is picked up by the
+/// next check rather than missed
+pub(crate) fn load(path: &str) -> Result<(String, Stamp)> {
+    let read_error = |e| AcfError::reading(path, &e);
+
+    let mut file = fs::File::open(path).map_err(read_error)?;
+    let meta = file.metadata().map_err(read_error)?;
+    let modified = meta.modified().map_err(read_error)?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).map_err(read_error)?;
+
+    let stamp = Stamp {
+        modified,
+        len: meta.len(),
+        hash: fnv1a(contents.as_bytes()),
+    };
+
+    Ok((contents, stamp))
+}
+
+/// Hashes bytes with 64-bit FNV-1a, which is stable across platforms and releases
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Copies the 730 fixture to a temporary path unique to the test and process
+#[cfg(test)]
+pub(crate) fn temp_copy(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("acf-parser-{}-{}.acf", name, std::process::id()));
+    fs::copy("./acfs/appmanifest_730.acf", &path).unwrap();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load_matches_metadata() {
+        let path = temp_copy("stamp-load");
+        let path = path.to_str().unwrap();
+        let loaded = load(path);
+        let metadata = Stamp::of(path);
+        fs::remove_file(path).unwrap();
+
+        let (contents, stamp) = loaded.unwrap();
+        assert_eq!(stamp.len, contents.len() as u64);
+        assert_eq!(stamp.hash, fnv1a(contents.as_bytes()));
+        assert!(stamp.matches_metadata(&metadata.unwrap()));
+        assert!(Stamp::of(path).unwrap_err().is_not_found());
+        assert!(load(path).unwrap_err().is_not_found());
+    }
+}
@@ -0,0 +1,107 @@
+//! Enable the `test-strategies` feature to generate documents in property tests:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! acf-parser = { version = "*", features = ["test-strategies"] }
+//! ```
+//!
+//! ```ignore
+//! use acf_parser::strategies::{acf, TreeConfig};
+//! use proptest::prelude::*;
+//!
+//! proptest! {
+//!     #[test]
+//!     fn handles_any_manifest(manifest in acf(&TreeConfig::default())) {
+//!         my_tool::inspect(&manifest);
+//!     }
+//! }
+//! ```
+//!
+//! Every generated tree is valid: serializing it with `to_vdf_string` and parsing the result
+//! yields the same tree
+
+use crate::expressions::Expressions;
+use crate::parser::{Acf, Entry};
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+/// Limits on the shape of generated trees
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TreeConfig {
+    /// Maximum nesting depth of entries, where root entries have a depth of one
+    pub max_depth: u32,
+
+    /// Maximum number of root entries. At least one is always generated
+    pub max_roots: usize,
+
+    /// Maximum number of expressions in a single entry
+    pub max_keys: usize,
+
+    /// Maximum number of sub-entries of a single entry
+    pub max_children: usize,
+}
+
+impl Default for TreeConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 4,
+            max_roots: 2,
+            max_keys: 8,
+            max_children: 4,
+        }
+    }
+}
+
+/// Generates strings that are valid inside a string literal
+///
+/// Mixes identifiers with awkward content: empty strings, backslashes, braces, comment markers,
+/// whitespace and control characters, and non-ASCII text. The quote character is never produced,
+/// and backslashes only appear as `\n` or `\\` since the parser decodes `\t` into a tab
+pub fn acf_string() -> impl Strategy<Value = String> {
+    prop_oneof![
+        Just(String::new()),
+        "[A-Za-z0-9_]{1,12}",
+        r"(\\[n\\]|[{}]|//|[ \t\r\n]|é|日本|🎮){1,8}",
+        r#"[^"\\]{0,16}"#,
+    ]
+}
+
+/// Generates the expressions of a single entry
+pub fn expressions(max_keys: usize) -> impl Strategy<Value = Expressions> {
+    vec((acf_string(), acf_string()), 0..=max_keys)
+        .prop_map(|pairs| pairs.into_iter().collect())
+}
+
+/// Generates an entry, including nested sub-entries, within the given limits
+pub fn entry(config: &TreeConfig) -> impl Strategy<Value = Entry> {
+    let max_keys = config.max_keys;
+    let max_children = config.max_children;
+
+    let leaf = (acf_string(), expressions(max_keys)).prop_map(|(name, expressions)| Entry {
+        name,
+        expressions,
+        entries: Vec::new(),
+        comments: Vec::new(),
+    });
+
+    leaf.prop_recursive(
+        config.max_depth.saturating_sub(1),
+        64,
+        max_children as u32,
+        move |inner| {
+            (acf_string(), expressions(max_keys), vec(inner, 0..=max_children)).prop_map(
+                |(name, expressions, entries)| Entry {
+                    name,
+                    expressions,
+                    entries,
+                    comments: Vec::new(),
+                },
+            )
+        },
+    )
+}
+
+/// Generates a whole document within the given limits
+pub fn acf(config: &TreeConfig) -> impl Strategy<Value = Acf> {
+    vec(entry(config), 1..=config.max_roots.max(1)).prop_map(|entries| Acf { entries })
+}
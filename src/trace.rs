@@ -0,0 +1,52 @@
+use crate::errors::AcfError;
+use crate::parser::{Acf, Entry, ParseOptions};
+use std::time::Instant;
+use tracing::Span;
+
+/// Records the outcome of reading a file
+pub(crate) fn read(path: &str, result: Result<usize, &std::io::Error>, started: Instant) {
+    match result {
+        Ok(bytes) => tracing::debug!(path, bytes, elapsed = ?started.elapsed(), "read file"),
+        Err(e) => tracing::debug!(path, error = %e, "failed to read file"),
+    }
+}
+
+/// Creates the span around a parse of in-memory content, which [`parsed`] completes
+pub(crate) fn parse_span(bytes: usize, options: &ParseOptions) -> Span {
+    tracing::debug_span!("parse_contents", bytes, ?options, entries = tracing::field::Empty)
+}
+
+/// Records the outcome of a parse on its span
+pub(crate) fn parsed(span: &Span, result: &Result<Acf, AcfError>, started: Instant) {
+    match result {
+        Ok(acf) => {
+            let (entries, keys) = count(&acf.entries);
+            span.record("entries", entries);
+            tracing::debug!(entries, keys, elapsed = ?started.elapsed(), "parsed");
+        }
+        Err(e) => {
+            let reason = e.as_parse_error();
+            tracing::debug!(error = %e, ?reason, elapsed = ?started.elapsed(), "parse failed");
+        }
+    }
+}
+
+/// Creates the span around serialization, which [`serialized`] completes
+pub(crate) fn serialize_span() -> Span {
+    let empty = tracing::field::Empty;
+    tracing::debug_span!("to_vdf_string", entries = empty, bytes = empty)
+}
+
+/// Records the size of serialized output on its span
+pub(crate) fn serialized(span: &Span, entries: &[Entry], out: &str) {
+    span.record("entries", count(entries).0);
+    span.record("bytes", out.len());
+}
+
+/// Counts entries and expressions in a tree
+fn count(entries: &[Entry]) -> (usize, usize) {
+    entries.iter().fold((0, 0), |(entries, keys), entry| {
+        let (sub_entries, sub_keys) = count(&entry.entries);
+        (entries + 1 + sub_entries, keys + entry.expressions.len() + sub_keys)
+    })
+}
@@ -0,0 +1,215 @@
+use crate::errors::*;
+use crate::parser::Entry;
+use std::fmt::Display;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Error handling
+type Result<T> = std::result::Result<T, AcfError>;
+
+impl Entry {
+    /// Returns the value of a Unix timestamp field (e.g. `lastupdated`) as a [`SystemTime`]
+    ///
+    /// The value must be a whole number of seconds since the Unix epoch
+    pub fn get_unix_time(&self, key: &str) -> Result<SystemTime> {
+        let secs: u64 = self.parse_value(key)?;
+
+        UNIX_EPOCH
+            .checked_add(Duration::from_secs(secs))
+            .ok_or_else(|| invalid(key, &self.expressions[key], "timestamp out of range"))
+    }
+
+    /// Returns the value of an address field (e.g. `1.2.3.4:27015`) as a [`SocketAddr`]
+    ///
+    /// IPv6 addresses must be bracketed, as in `[::1]:27015`
+    pub fn get_socket_addr(&self, key: &str) -> Result<SocketAddr> {
+        self.parse_value(key)
+    }
+
+    /// Returns the value of an IP address field (e.g. `1.2.3.4` or `::1`) as an [`IpAddr`]
+    pub fn get_ip_addr(&self, key: &str) -> Result<IpAddr> {
+        self.parse_value(key)
+    }
+
+    /// Returns the value of a base64 encoded field as bytes
+    ///
+    /// The value must use the standard alphabet (`A-Z`, `a-z`, `0-9`, `+`, `/`). Trailing `=`
+    /// padding is optional, but if present must pad the value to a multiple of four characters
+    pub fn get_base64(&self, key: &str) -> Result<Vec<u8>> {
+        let value = self.value(key)?;
+        decode_base64(value).map_err(|reason| invalid(key, value, reason))
+    }
+
+    /// Returns the value of a hex encoded field as bytes
+    ///
+    /// Digits may be upper or lower case, two per byte, with no prefix or separators
+    pub fn get_hex(&self, key: &str) -> Result<Vec<u8>> {
+        let value = self.value(key)?;
+        decode_hex(value).map_err(|reason| invalid(key, value, reason))
+    }
+
+    /// Returns the value of the given key parsed using its [`FromStr`] implementation, or
+    /// `default` when the key is missing or its value doesn't parse
+    pub fn get_as_or<T: FromStr>(&self, key: &str, default: T) -> T {
+        self.expressions
+            .get(key)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    /// Parses the value of the given key using its [`FromStr`] implementation
+    pub(crate) fn parse_value<T>(&self, key: &str) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let value = self.value(key)?;
+        value.parse().map_err(|e: T::Err| invalid(key, value, e))
+    }
+
+    /// Returns the value of the given key, or a missing value error
+    fn value(&self, key: &str) -> Result<&str> {
+        match self.expressions.get(key) {
+            Some(value) => Ok(value),
+            None => Err(AcfError::Value(ValueError::missing(key))),
+        }
+    }
+}
+
+/// Decodes standard base64, with or without padding
+fn decode_base64(value: &str) -> std::result::Result<Vec<u8>, String> {
+    let digits = value.trim_end_matches('=');
+    let padding = value.len() - digits.len();
+    if padding > 2 || (padding > 0 && !value.len().is_multiple_of(4)) || digits.len() % 4 == 1 {
+        return Err("invalid base64 length".to_owned());
+    }
+
+    let mut out = Vec::with_capacity(digits.len() * 3 / 4);
+    let mut buf = 0u32;
+    let mut bits = 0;
+
+    for c in digits.chars() {
+        let sextet = match c {
+            'A'..='Z' => c as u32 - 'A' as u32,
+            'a'..='z' => c as u32 - 'a' as u32 + 26,
+            '0'..='9' => c as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            _ => return Err(format!("invalid base64 character '{}'", c)),
+        };
+
+        buf = (buf << 6) | sextet;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buf >> bits) as u8);
+            buf &= (1 << bits) - 1;
+        }
+    }
+
+    Ok(out)
+}
+
+/// Decodes hex digits, two per byte
+fn decode_hex(value: &str) -> std::result::Result<Vec<u8>, String> {
+    if !value.len().is_multiple_of(2) {
+        return Err("odd number of hex digits".to_owned());
+    }
+
+    let digit = |c: char| c.to_digit(16).ok_or_else(|| format!("invalid hex digit '{}'", c));
+    let mut chars = value.chars();
+    let mut out = Vec::with_capacity(value.len() / 2);
+
+    while let (Some(high), Some(low)) = (chars.next(), chars.next()) {
+        out.push(((digit(high)? << 4) | digit(low)?) as u8);
+    }
+
+    Ok(out)
+}
+
+/// Creates an invalid value error
+fn invalid(key: &str, value: &str, reason: impl Display) -> AcfError {
+    AcfError::Value(ValueError::invalid(key, value, reason.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::*;
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn unix_time() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let root_entry = &acf.entries[0];
+
+        let updated = root_entry.get_unix_time("lastupdated").unwrap();
+        assert!(updated > UNIX_EPOCH);
+        assert_eq!(updated, UNIX_EPOCH + Duration::from_secs(1758252443));
+
+        let err = root_entry.get_unix_time("missing").unwrap_err();
+        assert!(err.as_value_error().unwrap().is_missing());
+
+        let err = root_entry.get_unix_time("name").unwrap_err();
+        assert!(err.as_value_error().unwrap().is_invalid());
+    }
+
+    #[test]
+    fn get_as_or() {
+        let mut acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+        let root_entry = &mut acf.entries[0];
+
+        assert_eq!(root_entry.get_as_or("BytesToDownload", 0u64), 51584);
+        assert_eq!(root_entry.get_as_or("name", 0u64), 0);
+        assert_eq!(root_entry.get_as_or("StateFlags", 0u8), 4);
+
+        root_entry.expressions.remove("BytesToDownload");
+        assert_eq!(root_entry.get_as_or("BytesToDownload", 0u64), 0);
+        let installdir = root_entry.get_as_or("installdir", String::new());
+        assert_eq!(installdir, "Counter-Strike Global Offensive");
+    }
+
+    #[test]
+    fn addresses() {
+        let src = r#""Server" { "addr" "1.2.3.4:27015" "ip" "1.2.3.4" "bad" "1.2.3:x" }"#;
+        let acf = parse_acf_str(src).unwrap();
+        let entry = &acf.entries[0];
+
+        let ip = IpAddr::V4(Ipv4Addr::new(1, 2, 3, 4));
+        assert_eq!(entry.get_socket_addr("addr").unwrap(), SocketAddr::new(ip, 27015));
+        assert_eq!(entry.get_ip_addr("ip").unwrap(), ip);
+
+        let err = entry.get_socket_addr("bad").unwrap_err();
+        assert!(err.as_value_error().unwrap().is_invalid());
+        assert!(err.to_string().contains("1.2.3:x"));
+        assert!(entry.get_ip_addr("addr").is_err());
+        assert!(entry.get_ip_addr("missing").unwrap_err().as_value_error().unwrap().is_missing());
+    }
+
+    #[test]
+    fn bytes() {
+        let src = concat!(
+            r#""Blob" { "b64" "aGVsbG8gd29ybGQ=" "unpadded" "aGk" "hex" "deadBEEF" "#,
+            r#""bad_b64" "aGk*" "short" "a" "bad_hex" "0xff" "odd" "abc" "empty" "" }"#,
+        );
+        let acf = parse_acf_str(src).unwrap();
+        let entry = &acf.entries[0];
+
+        assert_eq!(entry.get_base64("b64").unwrap(), b"hello world");
+        assert_eq!(entry.get_base64("unpadded").unwrap(), b"hi");
+        assert_eq!(entry.get_hex("hex").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert!(entry.get_base64("empty").unwrap().is_empty());
+        assert!(entry.get_hex("empty").unwrap().is_empty());
+
+        let err = entry.get_base64("bad_b64").unwrap_err();
+        assert!(err.to_string().contains("invalid base64 character '*'"));
+        assert!(entry.get_base64("short").unwrap_err().as_value_error().unwrap().is_invalid());
+        assert!(entry.get_base64("hex").is_ok());
+
+        let err = entry.get_hex("bad_hex").unwrap_err();
+        assert!(err.to_string().contains("invalid hex digit 'x'"));
+        assert!(entry.get_hex("odd").unwrap_err().as_value_error().unwrap().is_invalid());
+        assert!(entry.get_hex("missing").unwrap_err().as_value_error().unwrap().is_missing());
+    }
+}
@@ -0,0 +1,95 @@
+//! Build for the browser with the `wasm-bindgen` feature enabled, then generate the JS glue:
+//!
+//! ```text
+//! cargo rustc --release --target wasm32-unknown-unknown --features wasm-bindgen --crate-type cdylib
+//! wasm-bindgen --target web --out-dir pkg target/wasm32-unknown-unknown/release/acf_parser.wasm
+//! ```
+//!
+//! There is no filesystem on `wasm32-unknown-unknown`, so the path based parsers return
+//! [`AcfError::Read`] there. Parse the text of dropped or fetched files instead
+
+use crate::errors::{AcfError, ParseError};
+use crate::parser::{parse_acf_str, Entry};
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+/// Parses ACF text into a plain JS object
+///
+/// The result has the shape `{ entries: [...] }`, where every entry is
+/// `{ name, expressions, entries }`. Expressions are an array of `[key, value]` pairs so source
+/// order and repeated keys are preserved
+///
+/// Errors are thrown as `{ kind, message, start, end }`. `kind` is one of `"read"`,
+/// `"not_found"`, `"write"`, `"parse"`, `"value"`, `"serde"`, or `"unknown"`, and `start`/`end`
+/// are byte offsets into `text`, present only when the error has a location
+#[wasm_bindgen]
+pub fn parse_acf_js(text: &str) -> Result<JsValue, JsValue> {
+    let acf = parse_acf_str(text).map_err(|e| error_to_js(&e))?;
+
+    let root = Object::new();
+    set(&root, "entries", &entries_to_js(&acf.entries));
+
+    Ok(root.into())
+}
+
+/// Converts a list of entries into a JS array
+fn entries_to_js(entries: &[Entry]) -> Array {
+    entries.iter().map(entry_to_js).collect()
+}
+
+/// Converts an entry into a JS object
+fn entry_to_js(entry: &Entry) -> Object {
+    let expressions = entry
+        .expressions
+        .iter()
+        .map(|(key, value)| Array::of2(&JsValue::from_str(key), &JsValue::from_str(value)))
+        .collect::<Array>();
+
+    let obj = Object::new();
+    set(&obj, "name", &JsValue::from_str(&entry.name));
+    set(&obj, "expressions", &expressions);
+    set(&obj, "entries", &entries_to_js(&entry.entries));
+
+    obj
+}
+
+/// Converts an error into a JS object
+fn error_to_js(err: &AcfError) -> JsValue {
+    let kind = if err.is_read() {
+        "read"
+    } else if err.is_not_found() {
+        "not_found"
+    } else if err.is_write() {
+        "write"
+    } else if err.is_parse() {
+        "parse"
+    } else if err.is_value() {
+        "value"
+    } else if err.is_serde() {
+        "serde"
+    } else {
+        "unknown"
+    };
+
+    let message = match err.as_parse_error() {
+        Some(e) => e.to_string(),
+        None => err.to_string(),
+    };
+
+    let obj = Object::new();
+    set(&obj, "kind", &JsValue::from_str(kind));
+    set(&obj, "message", &JsValue::from_str(&message));
+
+    if let Some(span) = err.as_parse_error().and_then(ParseError::span) {
+        set(&obj, "start", &JsValue::from(span.start));
+        set(&obj, "end", &JsValue::from(span.end));
+    }
+
+    obj.into()
+}
+
+/// Sets a property on a plain object
+fn set(target: &Object, key: &str, value: &JsValue) {
+    // Defining a property on a fresh, unfrozen object can't fail
+    let _ = Reflect::set(target, &JsValue::from_str(key), value);
+}
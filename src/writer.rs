@@ -0,0 +1,179 @@
+use crate::serializer::write_quoted;
+use std::io::{self, Write};
+
+/// Writes ACF text to an [`io::Write`] one entry at a time, without building a tree
+///
+/// Output uses the same layout as [`Acf::to_vdf_string`], with indentation tracked as entries are
+/// opened and closed. The parser requires an entry's expressions to precede its sub-entries, and
+/// every expression to sit within an entry, so calls that would break either rule fail with
+/// [`io::ErrorKind::InvalidInput`] without writing anything
+///
+/// ```ignore
+/// let mut writer = VdfWriter::new(file);
+/// writer.begin_entry("AppState")?;
+/// writer.expression("appid", "730")?;
+/// writer.end_entry()?;
+/// writer.finish()?;
+/// ```
+///
+/// [`Acf::to_vdf_string`]: crate::parser::Acf::to_vdf_string
+#[derive(Debug)]
+pub struct VdfWriter<W: Write> {
+    /// Destination of the output
+    inner: W,
+
+    /// Whether each open entry has had a sub-entry written, outermost first
+    open: Vec<bool>,
+
+    /// Scratch buffer each line is formatted into
+    line: String,
+}
+
+impl<W: Write> VdfWriter<W> {
+    /// Creates a writer with no open entries
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            open: Vec::new(),
+            line: String::new(),
+        }
+    }
+
+    /// Returns the number of entries currently open
+    pub fn depth(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Writes an entry's name and opening brace, nesting it within the current entry
+    pub fn begin_entry(&mut self, name: &str) -> io::Result<()> {
+        if let Some(has_children) = self.open.last_mut() {
+            *has_children = true;
+        }
+
+        self.line.clear();
+        write_indent(&mut self.line, self.open.len());
+        write_quoted(&mut self.line, name);
+        self.line.push('\n');
+        write_indent(&mut self.line, self.open.len());
+        self.line.push_str("{\n");
+        self.open.push(false);
+
+        self.inner.write_all(self.line.as_bytes())
+    }
+
+    /// Writes an expression within the current entry
+    pub fn expression(&mut self, key: &str, value: &str) -> io::Result<()> {
+        let Some(&has_children) = self.open.last() else {
+            return Err(invalid_input("expressions must be written within an entry"));
+        };
+        if has_children {
+            return Err(invalid_input(
+                "expressions must precede an entry's sub-entries",
+            ));
+        }
+
+        self.line.clear();
+        write_indent(&mut self.line, self.open.len());
+        write_quoted(&mut self.line, key);
+        self.line.push_str("\t\t");
+        write_quoted(&mut self.line, value);
+        self.line.push('\n');
+
+        self.inner.write_all(self.line.as_bytes())
+    }
+
+    /// Writes the closing brace of the current entry
+    pub fn end_entry(&mut self) -> io::Result<()> {
+        if self.open.pop().is_none() {
+            return Err(invalid_input("no entry is open"));
+        }
+
+        self.line.clear();
+        write_indent(&mut self.line, self.open.len());
+        self.line.push_str("}\n");
+
+        self.inner.write_all(self.line.as_bytes())
+    }
+
+    /// Flushes the output and returns the underlying writer
+    ///
+    /// Fails if any entry is still open, as the output would be incomplete
+    pub fn finish(mut self) -> io::Result<W> {
+        if !self.open.is_empty() {
+            return Err(invalid_input("entries are still open"));
+        }
+        self.inner.flush()?;
+
+        Ok(self.inner)
+    }
+}
+
+/// Writes indentation for the given nesting depth
+fn write_indent(out: &mut String, depth: usize) {
+    (0..depth).for_each(|_| out.push('\t'));
+}
+
+/// Creates an error for a call that would produce invalid output
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::*;
+
+    #[test]
+    fn write_document() {
+        let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+
+        let mut writer = VdfWriter::new(Vec::new());
+        for entry in &acf.entries {
+            write_entry(&mut writer, entry);
+        }
+        let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        assert_eq!(out, acf.to_vdf_string());
+        assert_eq!(parse_acf_str(&out).unwrap(), acf);
+    }
+
+    /// Writes an entry and its sub-entries through the writer
+    fn write_entry(writer: &mut VdfWriter<Vec<u8>>, entry: &Entry) {
+        writer.begin_entry(&entry.name).unwrap();
+        for (key, value) in &entry.expressions {
+            writer.expression(key, value).unwrap();
+        }
+        for sub_entry in &entry.entries {
+            write_entry(writer, sub_entry);
+        }
+        writer.end_entry().unwrap();
+    }
+
+    #[test]
+    fn rejects_invalid_calls() {
+        let mut writer = VdfWriter::new(Vec::new());
+        let err = writer.expression("appid", "730").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(writer.end_entry().is_err());
+
+        writer.begin_entry("AppState").unwrap();
+        writer.expression("appid", "730").unwrap();
+        writer.begin_entry("UserConfig").unwrap();
+        writer.end_entry().unwrap();
+        assert!(writer.expression("name", "late").is_err());
+        assert_eq!(writer.depth(), 1);
+
+        writer.begin_entry("MountedConfig").unwrap();
+        assert!(VdfWriter::new(Vec::new()).finish().unwrap().is_empty());
+        writer.end_entry().unwrap();
+        writer.end_entry().unwrap();
+
+        let out = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let acf = parse_acf_str(&out).unwrap();
+        assert_eq!(
+            acf.entries[0].child_names(),
+            ["UserConfig", "MountedConfig"]
+        );
+        assert!(!out.contains("late"));
+    }
+}
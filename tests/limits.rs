@@ -0,0 +1,140 @@
+//! Resource limit tests
+//!
+//! These live in their own test binary so the counting allocator only observes this file's
+//! tests. Allocations are tracked per thread, so tests running in parallel don't interfere
+
+use acf_parser::errors::{Limit, ParseError};
+use acf_parser::parser::{parse_acf_str_with_options, ParseOptions};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::fmt::Write;
+
+/// Upper bound on bytes a rejected parse may allocate
+const ALLOCATION_BUDGET: usize = 64 * 1024;
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATED.try_with(|count| count.set(count.get() + layout.size()));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Runs `f`, returning its output and the number of bytes allocated on this thread meanwhile
+fn allocated_during<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATED.with(Cell::get);
+    let output = f();
+    (output, ALLOCATED.with(Cell::get) - before)
+}
+
+/// Asserts that parsing `src` fails on the given limit without allocating much
+fn assert_rejected(src: &str, options: ParseOptions, expected: Limit) {
+    let (result, allocated) = allocated_during(|| parse_acf_str_with_options(src, &options));
+
+    match result.unwrap_err().as_parse_error() {
+        Some(ParseError::LimitExceeded { which, .. }) => assert_eq!(*which, expected),
+        other => panic!("unexpected error: {:?}", other),
+    }
+    assert!(
+        allocated < ALLOCATION_BUDGET,
+        "allocated {} bytes for a {} byte input",
+        allocated,
+        src.len()
+    );
+}
+
+#[test]
+fn total_keys() {
+    let mut src = String::from("\"Root\"\n{\n");
+    for i in 0..200_000 {
+        let _ = writeln!(src, "\t\"key{}\"\t\t\"value\"", i);
+    }
+    src.push('}');
+
+    let options = ParseOptions {
+        max_total_keys: Some(100),
+        ..Default::default()
+    };
+    assert_rejected(&src, options, Limit::TotalKeys);
+}
+
+#[test]
+fn total_entries() {
+    let mut src = String::from("\"Root\"\n{\n");
+    for i in 0..100_000 {
+        let _ = writeln!(src, "\t\"entry{}\"\n\t{{\n\t}}", i);
+    }
+    src.push('}');
+
+    let options = ParseOptions {
+        max_total_entries: Some(50),
+        ..Default::default()
+    };
+    assert_rejected(&src, options, Limit::TotalEntries);
+}
+
+#[test]
+fn value_length() {
+    let src = format!("\"Root\"\n{{\n\t\"key\"\t\t\"{}\"\n}}", "x".repeat(8 * 1024 * 1024));
+
+    let options = ParseOptions {
+        max_value_len: Some(1024),
+        ..Default::default()
+    };
+    assert_rejected(&src, options, Limit::ValueLength);
+}
+
+#[test]
+fn expressions_per_entry() {
+    let mut src = String::from("\"Root\"\n{\n\t\"Small\"\n\t{\n\t\t\"key\"\t\t\"value\"\n\t}\n");
+    for i in 0..200_000 {
+        let _ = writeln!(src, "\t\"key{}\"\t\t\"value\"", i);
+    }
+    src.push('}');
+
+    let options = ParseOptions {
+        max_expressions_per_entry: Some(100),
+        ..Default::default()
+    };
+    assert_rejected(&src, options, Limit::ExpressionsPerEntry);
+}
+
+#[test]
+fn input_bytes() {
+    let src = "\"a\" { \"\" \"".repeat(1024 * 1024);
+
+    let options = ParseOptions {
+        max_input_bytes: Some(64 * 1024),
+        max_depth: Some(usize::MAX),
+        ..Default::default()
+    };
+    assert_rejected(&src, options, Limit::InputBytes);
+}
+
+#[test]
+fn within_limits() {
+    let src = "\"Root\"\n{\n\t\"key\"\t\t\"value\"\n\t\"Child\"\n\t{\n\t}\n}";
+    let options = ParseOptions {
+        max_input_bytes: Some(src.len()),
+        max_total_keys: Some(1),
+        max_total_entries: Some(2),
+        max_value_len: Some(5),
+        max_depth: Some(2),
+        max_expressions_per_entry: Some(1),
+        ..Default::default()
+    };
+    let acf = parse_acf_str_with_options(src, &options).unwrap();
+    assert_eq!(acf.entries[0].expressions["key"], "value");
+}
@@ -0,0 +1,98 @@
+//! Deterministic corpus of hostile inputs
+//!
+//! Every parsing entry point must return (`Ok` or `Err`) rather than panic or overflow the stack.
+//! The fuzz target in `fuzz/` explores the same surface with generated input
+
+use acf_parser::incremental::TextEdit;
+use acf_parser::prelude::*;
+use std::fs;
+
+/// Runs the input through every parsing entry point
+fn parse_everything(src: &str) {
+    let _ = parse_acf_str(src);
+    let options = ParseOptions {
+        max_total_keys: Some(16),
+        max_total_entries: Some(16),
+        max_value_len: Some(16),
+        max_depth: Some(4),
+        ..Default::default()
+    };
+    let _ = parse_acf_str_with_options(src, &options);
+
+    if let Ok(lazy) = parse_acf_lazy(src) {
+        let _ = lazy.to_acf();
+    }
+}
+
+fn fixture() -> String {
+    fs::read_to_string("./acfs/appmanifest_730.acf").unwrap()
+}
+
+#[test]
+fn truncated() {
+    let src = fixture();
+    (0..=src.len())
+        .filter(|&end| src.is_char_boundary(end))
+        .for_each(|end| parse_everything(&src[..end]));
+}
+
+#[test]
+fn nul_bytes() {
+    parse_everything("\0");
+    parse_everything("\"AppState\"\0{\0\"appid\"\0\"730\"\0}");
+    parse_everything("\"App\0State\"\n{\n\t\"app\0id\"\t\t\"7\u{0}30\"\n}\n");
+}
+
+#[test]
+fn binary() {
+    let bytes = (0..=255u8).cycle().take(64 * 1024).collect::<Vec<_>>();
+    parse_everything(&String::from_utf8_lossy(&bytes));
+}
+
+#[test]
+fn long_token() {
+    let token = "x".repeat(1024 * 1024);
+    parse_everything(&format!("\"{}\"", token));
+    parse_everything(&format!("\"Root\"\n{{\n\t\"key\"\t\t\"{}\"\n}}\n", token));
+    parse_everything(&format!("\"Root\"\n{{\n\t\"key\"\t\t\"{}", token));
+    parse_everything(&token);
+}
+
+#[test]
+fn deeply_nested() {
+    parse_everything(&"{".repeat(10_000));
+    parse_everything(&"\"a\"{".repeat(10_000));
+    parse_everything(&format!("{}{}", "\"a\"{".repeat(10_000), "}".repeat(10_000)));
+    parse_everything(&"}".repeat(10_000));
+}
+
+#[test]
+fn unbalanced() {
+    parse_everything("\"");
+    parse_everything("\"\"");
+    parse_everything("\"\"{");
+    parse_everything("\"\"{}}");
+    parse_everything("{}");
+    parse_everything("\"a\" \"b\"");
+    parse_everything("\"a\"{\"b\"}");
+    parse_everything("\"a\"{\"b\"{\"c\"\"d\"}\"e\"\"f\"}");
+}
+
+#[test]
+fn multi_byte() {
+    parse_everything("\"Стим\"\n{\n\t\"名前\"\t\t\"🎮\"\n}\n");
+    parse_everything("\"Стим\"\n{\n\t\"名前\"\t\t🎮\n}\n");
+}
+
+#[test]
+fn edits() {
+    let src = fixture();
+    let acf = parse_acf_str(&src).unwrap();
+
+    ["", "{", "}", "\"", "\"a\"{", "\0"].iter().for_each(|text| {
+        (0..src.len()).step_by(97).for_each(|start| {
+            let mut copy = acf.clone();
+            let _ = copy.reparse_edit(&src, &TextEdit::new(start..start, *text));
+        });
+    });
+}
@@ -0,0 +1,46 @@
+"""Tests for the Python bindings
+
+Run after `maturin develop --features python`, from the repository root:
+
+    pytest tests/python
+"""
+
+from pathlib import Path
+
+import pytest
+
+import acf_parser
+
+FIXTURE = Path(__file__).resolve().parents[2] / "acfs" / "appmanifest_730.acf"
+
+
+def test_parse_acf():
+    data = acf_parser.parse_acf(str(FIXTURE))
+    app_state = data["AppState"]
+    assert app_state["appid"] == "730"
+    assert app_state["name"] == "Counter-Strike 2"
+    assert app_state["InstalledDepots"]["731"]["size"] == "8"
+
+
+def test_parse_acf_str():
+    data = acf_parser.parse_acf_str(FIXTURE.read_text())
+    assert data == acf_parser.parse_acf(str(FIXTURE))
+
+
+def test_app_manifest():
+    manifest = acf_parser.AppManifest.from_file(str(FIXTURE))
+    assert manifest.appid == 730
+    assert manifest.name == "Counter-Strike 2"
+    assert manifest.size == 58882015666
+
+
+def test_parse_error_location():
+    with pytest.raises(acf_parser.AcfParseError) as info:
+        acf_parser.parse_acf_str('"AppState"\n{\n\t"appid"\t\t"730"\n\tjunk\n}\n')
+    assert info.value.line == 4
+    assert info.value.column == 2
+
+
+def test_missing_file():
+    with pytest.raises(OSError):
+        acf_parser.parse_acf("./acfs/missing.acf")
@@ -0,0 +1,122 @@
+//! Tracing instrumentation tests
+//!
+//! Run with `cargo test --features tracing`. A minimal subscriber records the names of spans, the
+//! fields recorded on them after creation, and the messages of events so the test doesn't depend
+//! on a particular subscriber implementation
+
+#![cfg(feature = "tracing")]
+
+use acf_parser::parser::{parse_acf, parse_acf_str};
+use std::fs;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+#[derive(Clone, Default)]
+struct Collector {
+    /// Names of the spans created, in order
+    spans: Arc<Mutex<Vec<String>>>,
+
+    /// Messages of the events emitted, in order
+    events: Arc<Mutex<Vec<String>>>,
+
+    /// Fields recorded on existing spans as `name=value`, in order
+    recorded: Arc<Mutex<Vec<String>>>,
+}
+
+/// Extracts the message of an event
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+/// Formats every field as `name=value`
+#[derive(Default)]
+struct Fields(Vec<String>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push(format!("{}={:?}", field.name(), value));
+    }
+}
+
+impl Subscriber for Collector {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut spans = self.spans.lock().unwrap();
+        spans.push(span.metadata().name().to_owned());
+        Id::from_u64(spans.len() as u64)
+    }
+
+    fn record(&self, _: &Id, values: &Record<'_>) {
+        let mut fields = Fields::default();
+        values.record(&mut fields);
+        self.recorded.lock().unwrap().extend(fields.0);
+    }
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut message = Message::default();
+        event.record(&mut message);
+        self.events.lock().unwrap().push(message.0);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn parse_file() {
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), || {
+        parse_acf("./acfs/appmanifest_730.acf").unwrap();
+    });
+
+    assert_eq!(*collector.spans.lock().unwrap(), ["parse_acf", "parse_contents"]);
+    assert_eq!(*collector.events.lock().unwrap(), ["read file", "parsed"]);
+    assert_eq!(*collector.recorded.lock().unwrap(), ["entries=12"]);
+}
+
+#[test]
+fn serialize() {
+    let acf = parse_acf("./acfs/appmanifest_730.acf").unwrap();
+    let bytes = fs::metadata("./acfs/appmanifest_730.acf").unwrap().len();
+
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), || {
+        acf.to_vdf_string();
+        acf.entries[0].entries[0].to_vdf_string();
+    });
+
+    assert_eq!(*collector.spans.lock().unwrap(), ["to_vdf_string", "to_vdf_string"]);
+    let recorded = collector.recorded.lock().unwrap();
+    assert_eq!(recorded[..2], ["entries=12".to_owned(), format!("bytes={}", bytes)]);
+    assert_eq!(recorded[2], "entries=8");
+}
+
+#[test]
+fn parse_failure() {
+    let collector = Collector::default();
+    tracing::subscriber::with_default(collector.clone(), || {
+        assert!(parse_acf_str("\"AppState\" {").is_err());
+        assert!(parse_acf("./acfs/missing.acf").is_err());
+    });
+
+    assert_eq!(
+        *collector.events.lock().unwrap(),
+        ["parse failed", "failed to read file"]
+    );
+}